@@ -1,6 +1,6 @@
 use bevy::prelude::*;
 use bevy::window::WindowMode;
-use kilter_brain::kilter_data::KilterData;
+use kilter_brain::data_loading::KilterDataSource;
 use kilter_brain::AppPlugin;
 
 #[cfg(target_os = "ios")]
@@ -11,14 +11,8 @@ extern "C" fn main_rs() {
 
 #[bevy_main]
 fn main() {
-    let kd = {
-        let mut kd = KilterData::default();
-        kd.json_update_reader(std::io::Cursor::new(include_str!("../../minimal.json")));
-        kd
-    };
-
     App::new()
-        .insert_resource(kd)
+        .insert_resource(KilterDataSource("minimal.json".to_string()))
         .add_plugins((
             DefaultPlugins.set(WindowPlugin {
                 primary_window: Some(Window {
@@ -1,46 +1,126 @@
+use std::time::Duration;
+
 use bevy::{
     input::gestures::PinchGesture,
-    picking::events::{Click, DragEnd, Pointer},
+    picking::{
+        events::{Click, DragEnd, DragStart, Move, Out, Pointer, Pressed, Released},
+        pointer::PointerButton,
+    },
     prelude::*,
 };
 
+use bevy_simple_text_input::TextInputInactive;
 use uuid::Uuid;
 
-use std::fmt::Write;
-
 use crate::{
     clipboard::PasteEvent,
-    kilter_board::{Board, KilterSettings, SelectedClimb},
-    kilter_data::{parse_placements_and_roles, Climb, KilterData},
+    history::{Edit, EditEvent},
+    kilter_board::{ActiveLayout, Board, ChangeClimbEvent, FramePlayback, SelectedClimb},
+    kilter_data::{Climb, ClimbOrigin, Frame, FramePlacement, Frames, FramesError, KilterData},
+    placement_index::PlacementIndex,
     placement_indicator::PlacementIndicator,
+    ui::{role_menu::OpenRoleMenu, toast_panel::ToastEvent},
 };
 
+/// Role positions in the order that clicking a placement cycles through them:
+/// any, foot only, start, finish.
+const ROLE_CYCLE_POSITIONS: [u32; 4] = [2, 4, 1, 3];
+
+/// How close to a hold, in world units, a drag must start to move it.
+const GRAB_RADIUS: f32 = 0.05;
+/// How long to hold a press on the board to open the role menu.
+const LONG_PRESS: Duration = Duration::from_millis(500);
+
+/// Keys that give the hovered placement the role at a position: start, hand,
+/// finish, foot.
+const ROLE_KEYS: [(KeyCode, KeyCode, u32); 4] = [
+    (KeyCode::Digit1, KeyCode::Numpad1, 1),
+    (KeyCode::Digit2, KeyCode::Numpad2, 2),
+    (KeyCode::Digit3, KeyCode::Numpad3, 3),
+    (KeyCode::Digit4, KeyCode::Numpad4, 4),
+];
+
 pub struct AuthoringPlugin;
 
 impl Plugin for AuthoringPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Update, (cycle, log_frames, on_paste));
+        app.init_resource::<DraggedHold>()
+            .init_resource::<HoveredPlacement>()
+            .init_resource::<PendingLongPress>()
+            .add_systems(
+                Update,
+                (
+                    cycle,
+                    log_frames,
+                    on_paste,
+                    track_hovered_placement,
+                    role_keys.after(track_hovered_placement),
+                    (start_long_press, long_press, right_click_menu),
+                    (start_hold_drag, move_hold_drag, end_hold_drag)
+                        .chain()
+                        .in_set(HoldDragSystems),
+                ),
+            );
     }
 }
 
+/// Starts, moves and drops dragged holds.
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct HoldDragSystems;
+
+/// The hold being dragged to another placement, if any. The camera doesn't
+/// pan while a hold is being dragged, or as it's dropped.
+#[derive(Resource, Default)]
+pub struct DraggedHold(Option<HoldDrag>);
+impl DraggedHold {
+    pub fn is_dragging(&self) -> bool {
+        self.0.is_some()
+    }
+}
+
+/// The placement under the pointer, if any.
+#[derive(Resource, Default)]
+pub struct HoveredPlacement(pub Option<u32>);
+
+/// A press on the board that opens the role menu if held long enough.
+#[derive(Resource, Default)]
+struct PendingLongPress(Option<(Timer, u32, Vec2)>);
+
+struct HoldDrag {
+    placement_id: u32,
+    role_id: u32,
+    /// Where the hold will be dropped.
+    target: u32,
+    /// Previews the hold at `target`.
+    ghost: Entity,
+}
+
 fn cycle(
-    mut commands: Commands,
-    mut indicator_query: Query<(Entity, &mut PlacementIndicator)>,
-    board_query: Query<(Entity, &GlobalTransform), With<Board>>,
+    indicator_query: Query<&PlacementIndicator>,
+    board_query: Query<&GlobalTransform, With<Board>>,
     mut click_events: EventReader<Pointer<Click>>,
     mut drag_end: EventReader<Pointer<DragEnd>>,
     mut pinch_events: EventReader<PinchGesture>,
     kilter: Res<KilterData>,
-    settings: Res<KilterSettings>,
+    index: Res<PlacementIndex>,
+    active: Res<ActiveLayout>,
+    selected: Res<SelectedClimb>,
+    playback: Res<FramePlayback>,
+    mut edits: EventWriter<EditEvent>,
 ) {
     let pinching = pinch_events.read().len() > 0;
     let drag_dist = drag_end.read().map(|e| e.event.distance).sum::<Vec2>();
 
     for event in click_events.read() {
-        let Ok((board_entity, board)) = board_query.get(event.target) else {
+        let Ok(board) = board_query.get(event.target) else {
             continue;
         };
 
+        // Other buttons and long presses open the role menu instead.
+        if event.event.button != PointerButton::Primary || event.event.duration >= LONG_PRESS {
+            continue;
+        }
+
         if drag_dist.length_squared() > 256.0 {
             continue;
         }
@@ -49,62 +129,48 @@ fn cycle(
             continue;
         }
 
-        let mut min: Option<(u32, Vec2, f32)> = None;
+        let Some(hit_position) = event.event.hit.position else {
+            continue;
+        };
 
-        for (id, placement) in &kilter.placements {
-            if placement.layout_id != 1 {
-                continue;
-            }
+        let cursor = Board::local_position(board, hit_position);
 
-            let Some(hole) = kilter.holes.get(&placement.hole_id) else {
-                continue;
-            };
+        // Clicks away from any hole do nothing.
+        let Some(placement_id) = index.nearest(cursor, |_| true) else {
+            continue;
+        };
 
-            let Some(hit_position) = event.event.hit.position else {
-                continue;
-            };
+        let Some((uuid, climb)) = kilter.climb_at(selected.0) else {
+            continue;
+        };
 
-            let pos = Vec2::new(hole.x as f32, hole.y as f32) * settings.scale + settings.offset;
+        let current = indicator_query
+            .iter()
+            .find(|p| p.placement_id == placement_id)
+            .map(|p| p.role_id);
 
-            let cursor = hit_position - board.translation();
+        // Determine the order of roles to cycle through.
 
-            let d_squared = pos.distance_squared(cursor.truncate());
+        let product_roles = kilter.sorted_placement_roles(active.product_id(&kilter).unwrap_or(1));
 
-            if min.is_none_or(|(_, _, min_d_squared)| d_squared < min_d_squared) {
-                min = Some((*id, pos, d_squared));
-            }
-        }
+        let mut roles: Vec<Option<u32>> = ROLE_CYCLE_POSITIONS
+            .iter()
+            .filter_map(|position| product_roles.iter().find(|r| r.position == *position))
+            .map(|r| Some(r.id))
+            .collect();
 
-        let Some((placement_id, _, _d_squared)) = min else {
+        let Some(Some(any_role_id)) = roles.first().copied() else {
+            warn!("No placement roles for layout {}", active.layout_id);
             continue;
         };
 
-        // TODO consider monitoring the selected climb's frame data directly
-        // and updating the indicators in a separate system.
-
-        let search = indicator_query
-            .iter_mut()
-            .find(|(_, p)| p.placement_id == placement_id);
-
-        // Determine the order of roles to cycle through.
+        roles.push(None);
 
         let first_role_id = kilter
             .placements
             .get(&placement_id)
             .and_then(|p| p.default_placement_role_id)
-            .unwrap_or(13);
-
-        let mut roles = [
-            // any
-            Some(13),
-            // foot only
-            Some(15),
-            // start
-            Some(12),
-            // finish
-            Some(14),
-            None,
-        ];
+            .unwrap_or(any_role_id);
 
         if let Some(first_pos) = roles.iter().position(|r| *r == Some(first_role_id)) {
             if first_pos != 0 {
@@ -112,31 +178,357 @@ fn cycle(
             }
         }
 
-        if let Some((entity, mut placement)) = search {
-            let current = roles
-                .iter()
-                .position(|r| *r == Some(placement.role_id))
-                .unwrap();
-            let next = roles.iter().cycle().nth(current + 1).unwrap();
-
-            if let Some(next) = next {
-                placement.role_id = *next;
-            } else {
-                commands.entity(entity).despawn();
+        // Skip roles the climb already has as many of as it may, e.g. a third
+        // start hold, even if it's the placement's default.
+        roles.retain(|r| r.is_none_or(|r| Some(r) == current || !kilter.role_is_full(climb, r)));
+
+        let next = match current {
+            Some(role_id) => {
+                let pos = roles
+                    .iter()
+                    .position(|r| *r == Some(role_id))
+                    .unwrap_or(roles.len() - 1);
+                *roles.iter().cycle().nth(pos + 1).unwrap()
             }
-        } else {
-            // TODO if there are already two start holds on the board,
-            // don't use that role even if it's the default.
-
-            let indicator = commands
-                .spawn(PlacementIndicator {
-                    placement_id,
-                    role_id: roles.first().unwrap().unwrap(),
-                })
-                .id();
-            commands.entity(board_entity).add_child(indicator);
+            None => roles[0],
+        };
+
+        // Nothing to cycle to, e.g. every role is full.
+        if next == current {
+            continue;
+        }
+
+        edits.write(EditEvent(Edit::SetHold {
+            uuid: uuid.clone(),
+            frame: playback.frame,
+            placement_id,
+            from: current,
+            to: next,
+            origin: climb.origin,
+        }));
+    }
+}
+
+/// Builds an edit giving `placement_id` the role `role_id` in the frame being
+/// shown, or removing it, or `None` if nothing would change.
+pub fn set_hold_edit(
+    kilter: &KilterData,
+    selected: &SelectedClimb,
+    playback: &FramePlayback,
+    placement_id: u32,
+    role_id: Option<u32>,
+) -> Option<Edit> {
+    let (uuid, climb) = kilter.climb_at(selected.0)?;
+
+    let from = climb
+        .frames
+        .get(playback.frame)
+        .and_then(|f| f.role_of(placement_id));
+
+    (from != role_id).then(|| Edit::SetHold {
+        uuid: uuid.clone(),
+        frame: playback.frame,
+        placement_id,
+        from,
+        to: role_id,
+        origin: climb.origin,
+    })
+}
+
+fn track_hovered_placement(
+    mut move_events: EventReader<Pointer<Move>>,
+    mut out_events: EventReader<Pointer<Out>>,
+    mut hovered: ResMut<HoveredPlacement>,
+    board_query: Query<&GlobalTransform, With<Board>>,
+    index: Res<PlacementIndex>,
+) {
+    if out_events.read().any(|e| board_query.contains(e.target)) {
+        hovered.0 = None;
+    }
+
+    let Some((board, hit_position)) = move_events
+        .read()
+        .filter_map(|e| Some((board_query.get(e.target).ok()?, e.event.hit.position?)))
+        .last()
+    else {
+        return;
+    };
+
+    let cursor = Board::local_position(board, hit_position);
+
+    let placement_id = index.nearest(cursor, |_| true);
+
+    if hovered.0 != placement_id {
+        hovered.0 = placement_id;
+    }
+}
+
+/// Sets the hovered placement's role with the number keys, or removes it with
+/// Delete or Backspace.
+fn role_keys(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    hovered: Res<HoveredPlacement>,
+    text_inputs: Query<&TextInputInactive>,
+    kilter: Res<KilterData>,
+    active: Res<ActiveLayout>,
+    selected: Res<SelectedClimb>,
+    playback: Res<FramePlayback>,
+    mut edits: EventWriter<EditEvent>,
+) {
+    let Some(placement_id) = hovered.0 else {
+        return;
+    };
+
+    // Keys belong to the text input being typed in.
+    if text_inputs.iter().any(|inactive| !inactive.0) {
+        return;
+    }
+
+    let role_id = if keyboard_input.any_just_pressed([KeyCode::Delete, KeyCode::Backspace]) {
+        None
+    } else {
+        let Some(position) = ROLE_KEYS
+            .iter()
+            .find(|(key, numpad_key, _)| keyboard_input.any_just_pressed([*key, *numpad_key]))
+            .map(|(_, _, position)| *position)
+        else {
+            return;
+        };
+
+        let product_id = active.product_id(&kilter).unwrap_or(1);
+        let Some(role) = kilter
+            .sorted_placement_roles(product_id)
+            .into_iter()
+            .find(|r| r.position == position)
+        else {
+            return;
+        };
+
+        Some(role.id)
+    };
+
+    if let Some(edit) = set_hold_edit(&kilter, &selected, &playback, placement_id, role_id) {
+        edits.write(EditEvent(edit));
+    }
+}
+
+fn start_long_press(
+    mut pressed_events: EventReader<Pointer<Pressed>>,
+    mut released_events: EventReader<Pointer<Released>>,
+    mut drag_events: EventReader<Pointer<DragStart>>,
+    mut pending: ResMut<PendingLongPress>,
+    board_query: Query<(), With<Board>>,
+    hovered: Res<HoveredPlacement>,
+) {
+    if released_events.read().next().is_some() || drag_events.read().next().is_some() {
+        pending.0 = None;
+    }
+
+    for event in pressed_events.read() {
+        if event.event.button != PointerButton::Primary || !board_query.contains(event.target) {
+            continue;
+        }
+
+        let Some(placement_id) = hovered.0 else {
+            continue;
+        };
+
+        pending.0 = Some((
+            Timer::new(LONG_PRESS, TimerMode::Once),
+            placement_id,
+            event.pointer_location.position,
+        ));
+    }
+}
+
+fn long_press(
+    mut pending: ResMut<PendingLongPress>,
+    time: Res<Time>,
+    mut menu_events: EventWriter<OpenRoleMenu>,
+) {
+    let Some((timer, placement_id, position)) = pending.0.as_mut() else {
+        return;
+    };
+
+    if timer.tick(time.delta()).finished() {
+        menu_events.write(OpenRoleMenu {
+            placement_id: *placement_id,
+            position: *position,
+        });
+        pending.0 = None;
+    }
+}
+
+fn right_click_menu(
+    mut click_events: EventReader<Pointer<Click>>,
+    board_query: Query<(), With<Board>>,
+    hovered: Res<HoveredPlacement>,
+    mut menu_events: EventWriter<OpenRoleMenu>,
+) {
+    for event in click_events.read() {
+        if event.event.button != PointerButton::Secondary || !board_query.contains(event.target) {
+            continue;
+        }
+
+        let Some(placement_id) = hovered.0 else {
+            continue;
+        };
+
+        menu_events.write(OpenRoleMenu {
+            placement_id,
+            position: event.pointer_location.position,
+        });
+    }
+}
+
+fn start_hold_drag(
+    mut commands: Commands,
+    mut events: EventReader<Pointer<DragStart>>,
+    mut dragged: ResMut<DraggedHold>,
+    board_query: Query<&GlobalTransform, With<Board>>,
+    indicator_query: Query<&PlacementIndicator>,
+    kilter: Res<KilterData>,
+    index: Res<PlacementIndex>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    for event in events.read() {
+        if event.event.button != PointerButton::Primary {
+            continue;
         }
+
+        let Ok(board) = board_query.get(event.target) else {
+            continue;
+        };
+
+        let Some(hit_position) = event.event.hit.position else {
+            continue;
+        };
+
+        let cursor = Board::local_position(board, hit_position);
+
+        let Some((indicator, pos)) = indicator_query
+            .iter()
+            .filter_map(|i| index.position(i.placement_id).map(|pos| (i, pos)))
+            .filter(|(_, pos)| pos.distance(cursor) < GRAB_RADIUS)
+            .min_by(|(_, a), (_, b)| a.distance(cursor).total_cmp(&b.distance(cursor)))
+        else {
+            continue;
+        };
+
+        let color = kilter
+            .placement_roles
+            .get(&indicator.role_id)
+            .and_then(|role| Srgba::hex(&role.led_color).ok())
+            .unwrap_or(Srgba::WHITE);
+
+        let ghost = commands
+            .spawn((
+                Mesh3d(meshes.add(Circle::new(0.03))),
+                MeshMaterial3d(materials.add(StandardMaterial {
+                    base_color: color.with_alpha(0.5).into(),
+                    alpha_mode: AlphaMode::Blend,
+                    unlit: true,
+                    ..default()
+                })),
+                Transform::from_translation(pos.extend(0.0004)),
+                Pickable::IGNORE,
+            ))
+            .id();
+        commands.entity(event.target).add_child(ghost);
+
+        dragged.0 = Some(HoldDrag {
+            placement_id: indicator.placement_id,
+            role_id: indicator.role_id,
+            target: indicator.placement_id,
+            ghost,
+        });
+    }
+}
+
+fn move_hold_drag(
+    mut events: EventReader<Pointer<Move>>,
+    mut dragged: ResMut<DraggedHold>,
+    board_query: Query<&GlobalTransform, With<Board>>,
+    indicator_query: Query<&PlacementIndicator>,
+    mut transforms: Query<&mut Transform>,
+    index: Res<PlacementIndex>,
+) {
+    if !dragged.is_dragging() {
+        events.clear();
+        return;
+    }
+
+    let Some(event) = events
+        .read()
+        .filter(|e| board_query.contains(e.target))
+        .last()
+    else {
+        return;
+    };
+
+    let (Ok(board), Some(hit_position)) = (board_query.get(event.target), event.event.hit.position)
+    else {
+        return;
+    };
+
+    let cursor = Board::local_position(board, hit_position);
+
+    let Some(drag) = dragged.0.as_mut() else {
+        return;
+    };
+
+    // Snap to the closest placement that doesn't already have a hold. Away
+    // from any, the hold stays where it last snapped.
+    let Some(target) = index.nearest(cursor, |id| {
+        id == drag.placement_id || !indicator_query.iter().any(|i| i.placement_id == id)
+    }) else {
+        return;
+    };
+
+    drag.target = target;
+
+    if let (Some(pos), Ok(mut transform)) = (index.position(target), transforms.get_mut(drag.ghost))
+    {
+        transform.translation = pos.extend(transform.translation.z);
+    }
+}
+
+fn end_hold_drag(
+    mut commands: Commands,
+    mut events: EventReader<Pointer<DragEnd>>,
+    mut dragged: ResMut<DraggedHold>,
+    kilter: Res<KilterData>,
+    selected: Res<SelectedClimb>,
+    playback: Res<FramePlayback>,
+    mut edits: EventWriter<EditEvent>,
+) {
+    if events.read().next().is_none() || !dragged.is_dragging() {
+        return;
+    }
+
+    let Some(drag) = dragged.0.take() else {
+        return;
+    };
+
+    commands.entity(drag.ghost).despawn();
+
+    if drag.target == drag.placement_id {
+        return;
     }
+
+    let Some((uuid, climb)) = kilter.climb_at(selected.0) else {
+        return;
+    };
+
+    edits.write(EditEvent(Edit::MoveHold {
+        uuid: uuid.clone(),
+        frame: playback.frame,
+        from: drag.placement_id,
+        to: drag.target,
+        role_id: drag.role_id,
+        origin: climb.origin,
+    }));
 }
 
 fn log_frames(
@@ -148,21 +540,22 @@ fn log_frames(
         return;
     }
 
-    let out: String = query.iter().fold(String::new(), |mut out, ind| {
-        let _ = write!(out, "{ind}");
-        out
-    });
+    let frame: Frame = query.iter().map(FramePlacement::from).collect();
 
-    info!("{out}");
+    info!("{frame}");
 }
 
 fn on_paste(
     mut events: EventReader<PasteEvent>,
-    mut selected: ResMut<SelectedClimb>,
-    mut kilter: ResMut<KilterData>,
+    selected: Res<SelectedClimb>,
+    kilter: Res<KilterData>,
+    active: Res<ActiveLayout>,
+    mut edits: EventWriter<EditEvent>,
+    mut toasts: EventWriter<ToastEvent>,
+    mut change_climb: EventWriter<ChangeClimbEvent>,
 ) {
     for event in events.read() {
-        let mut added = 0;
+        let mut climbs = vec![];
 
         let lines = event.0.split('\n');
         for (l, line) in lines.enumerate() {
@@ -171,37 +564,244 @@ fn on_paste(
                 continue;
             }
 
-            // Accept `name,frames` or `frames`.
-            let mut parts = line.rsplit(',');
-            let Some(frames) = parts.next() else {
+            if let Some(uuid) = share_link_uuid(line) {
+                match kilter
+                    .climb_index(uuid)
+                    .or_else(|| kilter.climb_index(&uuid.to_uppercase()))
+                {
+                    Some(idx) => {
+                        change_climb.write(ChangeClimbEvent::SelectByIndex(idx));
+                    }
+                    None => {
+                        toasts.write(ToastEvent(format!("Climb {uuid} not found")));
+                    }
+                }
                 continue;
+            }
+
+            let (name, frames) = match parse_pasted_line(line) {
+                Ok(parsed) => parsed,
+                Err(e) => {
+                    warn!("On pasted line {}: {}", l + 1, e);
+                    toasts.write(ToastEvent(format!("Pasted line {}: {}", l + 1, e)));
+                    continue;
+                }
             };
-            let name = parts.next().unwrap_or("Pasted Climb");
 
-            if let Err(e) = parse_placements_and_roles(frames) {
-                // TODO add UI toast thing to show errors
-                warn!("On pasted line {}: {}", l, e);
-                continue;
+            let duplicates = frames.duplicate_placements();
+            if !duplicates.is_empty() {
+                toasts.write(ToastEvent(format!(
+                    "Pasted line {}: duplicate placements {:?}",
+                    l + 1,
+                    duplicates
+                )));
             }
 
             let id = Uuid::new_v4().simple().to_string();
 
-            kilter.climbs.insert(
-                id.clone(),
-                Climb {
-                    uuid: id.clone(),
-                    setter_username: "User".to_string(),
-                    name: name.to_string(),
-                    frames: frames.to_string(),
-                    ..default()
-                },
-            );
+            climbs.push(Climb {
+                uuid: id,
+                setter_username: "User".to_string(),
+                name: name.to_string(),
+                frames_count: frames.len() as u32,
+                frames,
+                layout_id: active.layout_id,
+                origin: ClimbOrigin::Pasted,
+                ..default()
+            });
+        }
+
+        if !climbs.is_empty() {
+            toasts.write(ToastEvent(format!("Pasted {} climb(s)", climbs.len())));
+
+            let from = kilter
+                .climb_at(selected.0)
+                .map(|(uuid, _)| uuid.clone())
+                .unwrap_or_default();
+            edits.write(EditEvent(Edit::AddClimbs {
+                from,
+                climbs,
+                name: "paste",
+            }));
+        }
+    }
+}
+
+/// Parses a pasted line as `name<tab>frames`, `name,frames`, `frames` or a url
+/// containing a `frames=` query parameter.
+///
+/// Since `,` also separates frames, a name before a `,` ends at the first `,`
+/// that leaves valid frames, so names can contain commas too.
+fn parse_pasted_line(line: &str) -> Result<(&str, Frames), FramesError> {
+    if let Some(start) = line.find("frames=").map(|i| i + "frames=".len()) {
+        let value = line[start..].split('&').next().unwrap_or_default();
+        let (frames, offsets) = percent_decode(value);
+        return Frames::parse(&frames)
+            .map(|frames| ("Pasted Climb", frames))
+            .map_err(|mut err| {
+                err.offset = start + offsets[err.offset];
+                err.snippet = line[err.offset..]
+                    .chars()
+                    .take(FramesError::SNIPPET_LEN)
+                    .collect();
+                err
+            });
+    }
 
-            added += 1;
+    if let Some((name, frames)) = line.split_once('\t') {
+        return parse_named(name, frames);
+    }
+
+    let mut err = match Frames::parse(line) {
+        Ok(frames) => return Ok(("Pasted Climb", frames)),
+        Err(err) => err,
+    };
+
+    for (i, _) in line.match_indices(',') {
+        match parse_named(&line[..i], &line[i + 1..]) {
+            Ok(parsed) => return Ok(parsed),
+            // Report whichever attempt got furthest.
+            Err(e) if e.offset > err.offset => err = e,
+            Err(_) => {}
         }
+    }
 
-        if added > 0 {
-            selected.0 = kilter.climbs.len() - added;
+    Err(err)
+}
+
+/// Parses the `frames` that follow `name` and a one byte separator.
+fn parse_named<'a>(name: &'a str, frames: &str) -> Result<(&'a str, Frames), FramesError> {
+    Frames::parse(frames)
+        .map(|frames| (name.trim(), frames))
+        .map_err(|mut err| {
+            err.offset += name.len() + 1;
+            err
+        })
+}
+
+/// Decodes `%XX` escapes and `+` in a url query value, along with the offset
+/// into `value` of each decoded byte and of the end. Values that don't decode
+/// to UTF-8 are returned as is.
+fn percent_decode(value: &str) -> (String, Vec<usize>) {
+    let raw = value.as_bytes();
+    let mut bytes = Vec::with_capacity(raw.len());
+    let mut offsets = Vec::with_capacity(raw.len() + 1);
+    let mut i = 0;
+    while i < raw.len() {
+        offsets.push(i);
+        let escaped = raw
+            .get(i + 1..i + 3)
+            .filter(|_| raw[i] == b'%')
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match (escaped, raw[i]) {
+            (Some(byte), _) => {
+                bytes.push(byte);
+                i += 3;
+            }
+            (None, b'+') => {
+                bytes.push(b' ');
+                i += 1;
+            }
+            (None, byte) => {
+                bytes.push(byte);
+                i += 1;
+            }
         }
     }
+    offsets.push(raw.len());
+
+    match String::from_utf8(bytes) {
+        Ok(decoded) => (decoded, offsets),
+        Err(_) => (value.to_string(), (0..=raw.len()).collect()),
+    }
+}
+
+/// Extracts the climb uuid from a Kilter Board app share link like
+/// `https://kilterboardapp.com/climbs/<uuid>`.
+fn share_link_uuid(line: &str) -> Option<&str> {
+    let (_, rest) = line.split_once("kilterboardapp.com/climbs/")?;
+    let end = rest
+        .find(|c: char| !c.is_ascii_alphanumeric() && c != '-')
+        .unwrap_or(rest.len());
+    let uuid = &rest[..end];
+
+    (!uuid.is_empty()).then_some(uuid)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frames(s: &str) -> Frames {
+        Frames::parse(s).unwrap()
+    }
+
+    #[test]
+    fn pasted_frames_without_a_name() {
+        assert_eq!(
+            parse_pasted_line("p1r12,p2r13"),
+            Ok(("Pasted Climb", frames("p1r12,p2r13")))
+        );
+    }
+
+    #[test]
+    fn pasted_name_and_frames() {
+        assert_eq!(
+            parse_pasted_line("Crimpy,p1r12p2r13"),
+            Ok(("Crimpy", frames("p1r12p2r13")))
+        );
+        assert_eq!(
+            parse_pasted_line("Crimpy,p1r12,p2r13"),
+            Ok(("Crimpy", frames("p1r12,p2r13")))
+        );
+    }
+
+    #[test]
+    fn pasted_name_with_commas() {
+        assert_eq!(
+            parse_pasted_line("Slow, then fast,p1r12,p2r13"),
+            Ok(("Slow, then fast", frames("p1r12,p2r13")))
+        );
+        assert_eq!(
+            parse_pasted_line("Slow, then fast\tp1r12,p2r13"),
+            Ok(("Slow, then fast", frames("p1r12,p2r13")))
+        );
+    }
+
+    #[test]
+    fn pasted_url() {
+        assert_eq!(
+            parse_pasted_line("https://example.com/?frames=p1r12p2r13&x=1"),
+            Ok(("Pasted Climb", frames("p1r12p2r13")))
+        );
+        assert_eq!(
+            parse_pasted_line("https://example.com/?frames=p1r12%2Cp2r13%20p3r14+p4r15"),
+            Ok(("Pasted Climb", frames("p1r12,p2r13 p3r14 p4r15")))
+        );
+    }
+
+    #[test]
+    fn pasted_url_error_offsets_are_into_the_line() {
+        let line = "https://example.com/?x=1&frames=p1r12%2Cp2x13&y=2";
+        let err = parse_pasted_line(line).unwrap_err();
+        assert_eq!(&line[err.offset..], "x13&y=2");
+        assert_eq!(err.snippet, "x13&y=2");
+
+        let line = "https://example.com/?frames=p1r12%2Cp2r&y=2";
+        let err = parse_pasted_line(line).unwrap_err();
+        assert_eq!(&line[err.offset..], "&y=2");
+    }
+
+    #[test]
+    fn pasted_error_offsets_are_into_the_line() {
+        let line = "Slow, then fast,p1r12p2x13";
+        let err = parse_pasted_line(line).unwrap_err();
+        assert_eq!(&line[err.offset..], "x13");
+        assert_eq!(err.snippet, "x13");
+
+        let line = "Name\tp1r";
+        let err = parse_pasted_line(line).unwrap_err();
+        assert_eq!(err.offset, line.len());
+    }
 }
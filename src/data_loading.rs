@@ -0,0 +1,346 @@
+#[cfg(not(any(target_arch = "wasm32", target_os = "android", target_os = "ios")))]
+use bevy::asset::AssetPath;
+use bevy::{
+    asset::{io::Reader, AssetLoader, LoadContext, LoadState},
+    prelude::*,
+};
+use serde_json::Value;
+#[cfg(not(any(target_arch = "wasm32", target_os = "android", target_os = "ios")))]
+use std::path::{Path, PathBuf};
+
+use crate::{
+    kilter_data::{KilterData, KilterDataError, LoadReport},
+    ui::toast_panel::ToastEvent,
+};
+
+/// Loads [`KilterData`] through the asset server when a [`KilterDataSource`]
+/// is given, holding the app in [`DataState::Loading`] until it's ready and
+/// reloading it when the file changes.
+pub struct DataLoadingPlugin;
+
+impl Plugin for DataLoadingPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_state::<DataState>()
+            .enable_state_scoped_entities::<DataState>()
+            .init_resource::<KilterData>()
+            .init_resource::<AppliedUpdates>()
+            .init_asset::<KilterDataAsset>()
+            .init_asset_loader::<KilterDataLoader>()
+            .add_systems(Startup, start_loading)
+            .add_systems(Update, take_loaded_data);
+
+        #[cfg(not(any(target_arch = "wasm32", target_os = "android", target_os = "ios")))]
+        app.add_systems(OnEnter(DataState::Ready), write_snapshot);
+    }
+}
+
+/// Whether climb data has arrived. Systems that add to [`KilterData`], like
+/// loading the user's own climbs, wait for [`DataState::Ready`].
+#[derive(States, Default, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DataState {
+    #[default]
+    Loading,
+    Ready,
+}
+
+/// An asset path to load [`KilterData`] from, e.g. `minimal.json`. Without
+/// one, the app uses the [`KilterData`] resource as inserted.
+///
+/// JSON dumps are in the format of a JSON update, and `.kilter` files are
+/// snapshots written by [`KilterData::to_snapshot`]. On native, `.sqlite3`
+/// files are Kilter Board app databases. They're loaded along with the JSON
+/// updates in the directory named like [`UPDATES_PATH`] next to them, and
+/// saved as a snapshot named like [`SNAPSHOT_PATH`] once loaded.
+///
+/// [`UPDATES_PATH`]: crate::kilter_data::UPDATES_PATH
+/// [`SNAPSHOT_PATH`]: crate::kilter_data::SNAPSHOT_PATH
+#[derive(Resource, Debug, Clone)]
+pub struct KilterDataSource(pub String);
+
+/// JSON updates applied to [`KilterData`] after it loaded, e.g. by syncing.
+/// They're applied again when the data reloads so that they aren't lost.
+#[derive(Resource, Default)]
+pub struct AppliedUpdates(pub Vec<Value>);
+
+#[derive(Asset, TypePath)]
+pub struct KilterDataAsset {
+    pub data: KilterData,
+    /// Whether the data came from a database, and so is worth saving as a
+    /// snapshot.
+    pub from_database: bool,
+}
+
+#[derive(Resource)]
+struct KilterDataHandle(Handle<KilterDataAsset>);
+
+/// Set when the data loaded from a database, to save it as a snapshot.
+#[derive(Resource)]
+struct SnapshotPending;
+
+struct KilterDataLoader {
+    /// Lists the JSON updates next to a database.
+    #[cfg(not(any(target_arch = "wasm32", target_os = "android", target_os = "ios")))]
+    asset_server: AssetServer,
+}
+
+impl FromWorld for KilterDataLoader {
+    #[cfg_attr(
+        any(target_arch = "wasm32", target_os = "android", target_os = "ios"),
+        allow(unused_variables)
+    )]
+    fn from_world(world: &mut World) -> Self {
+        Self {
+            #[cfg(not(any(target_arch = "wasm32", target_os = "android", target_os = "ios")))]
+            asset_server: world.resource::<AssetServer>().clone(),
+        }
+    }
+}
+
+impl AssetLoader for KilterDataLoader {
+    type Asset = KilterDataAsset;
+    type Settings = ();
+    type Error = KilterDataError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        load_context: &mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut bytes = vec![];
+        reader.read_to_end(&mut bytes).await?;
+
+        let path = load_context.path();
+        let data = match path.extension().and_then(|ext| ext.to_str()) {
+            Some("kilter") => KilterData::from_snapshot(&bytes)?,
+            #[cfg(not(any(target_arch = "wasm32", target_os = "android", target_os = "ios")))]
+            Some("sqlite3") => {
+                return Ok(KilterDataAsset {
+                    data: self.load_database(&bytes, load_context).await?,
+                    from_database: true,
+                })
+            }
+            _ => {
+                let mut kilter = KilterData::default();
+                let report = kilter.json_update_reader(bytes.as_slice())?;
+                log_report(path.display(), &report);
+                kilter
+            }
+        };
+
+        Ok(KilterDataAsset {
+            data,
+            from_database: false,
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        EXTENSIONS
+    }
+}
+
+#[cfg(not(any(target_arch = "wasm32", target_os = "android", target_os = "ios")))]
+const EXTENSIONS: &[&str] = &["json", "kilter", "sqlite3"];
+#[cfg(any(target_arch = "wasm32", target_os = "android", target_os = "ios"))]
+const EXTENSIONS: &[&str] = &["json", "kilter"];
+
+#[cfg(not(any(target_arch = "wasm32", target_os = "android", target_os = "ios")))]
+impl KilterDataLoader {
+    /// Loads a database and the JSON updates since. Reading the updates
+    /// through `load_context` reloads the database when they change.
+    async fn load_database(
+        &self,
+        bytes: &[u8],
+        load_context: &mut LoadContext<'_>,
+    ) -> Result<KilterData, KilterDataError> {
+        let (mut kilter, report) = KilterData::from_sqlite_bytes(bytes)?;
+        log_report(load_context.path().display(), &report);
+
+        for path in self.update_paths(load_context.asset_path()).await {
+            let bytes = match load_context.read_asset_bytes(&path).await {
+                Ok(bytes) => bytes,
+                Err(e) => {
+                    warn!("Failed to read JSON update {}. {}", path, e);
+                    continue;
+                }
+            };
+
+            match kilter.json_update_reader(bytes.as_slice()) {
+                Ok(report) => log_report(&path, &report),
+                Err(e) => warn!("Failed to load JSON update {}. {}", path, e),
+            }
+        }
+
+        Ok(kilter)
+    }
+
+    /// The JSON updates next to the database at `db_path`, in order of their
+    /// file names.
+    async fn update_paths(&self, db_path: &AssetPath<'static>) -> Vec<AssetPath<'static>> {
+        use crate::kilter_data::UPDATES_PATH;
+        use bevy::{asset::io::AssetReaderError, tasks::futures_lite::StreamExt};
+
+        let dir = sibling(db_path.path(), UPDATES_PATH);
+        let source = match self.asset_server.get_source(db_path.source()) {
+            Ok(source) => source,
+            Err(e) => {
+                warn!("Failed to list JSON updates. {}", e);
+                return vec![];
+            }
+        };
+
+        let mut paths: Vec<_> = match source.reader().read_directory(&dir).await {
+            Ok(paths) => paths.collect().await,
+            Err(AssetReaderError::NotFound(_)) => return vec![],
+            Err(e) => {
+                warn!("Failed to list JSON updates. {}", e);
+                return vec![];
+            }
+        };
+        paths.retain(|path| path.extension().is_some_and(|ext| ext == "json"));
+        // Later updates must be applied after earlier ones.
+        paths.sort();
+
+        paths
+            .into_iter()
+            .map(|path| AssetPath::from(path).with_source(db_path.source().clone_owned()))
+            .collect()
+    }
+}
+
+/// The file named like the last part of `native_path`, next to `path`.
+#[cfg(not(any(target_arch = "wasm32", target_os = "android", target_os = "ios")))]
+fn sibling(path: &Path, native_path: &str) -> PathBuf {
+    path.with_file_name(Path::new(native_path).file_name().unwrap_or_default())
+}
+
+fn log_report(path: impl std::fmt::Display, report: &LoadReport) {
+    for e in &report.errors {
+        warn!("Skipped record in {}. {}", path, e);
+    }
+}
+
+fn start_loading(
+    mut commands: Commands,
+    source: Option<Res<KilterDataSource>>,
+    asset_server: Res<AssetServer>,
+    mut next_state: ResMut<NextState<DataState>>,
+) {
+    let Some(source) = source else {
+        next_state.set(DataState::Ready);
+        return;
+    };
+
+    commands.insert_resource(KilterDataHandle(asset_server.load(&source.0)));
+}
+
+/// Moves newly loaded data into [`KilterData`], both when it first loads and
+/// when the file changes, keeping climbs stored locally and updates applied
+/// since.
+fn take_loaded_data(
+    mut commands: Commands,
+    handle: Option<Res<KilterDataHandle>>,
+    state: Res<State<DataState>>,
+    asset_server: Res<AssetServer>,
+    applied: Res<AppliedUpdates>,
+    mut assets: ResMut<Assets<KilterDataAsset>>,
+    mut kilter: ResMut<KilterData>,
+    mut next_state: ResMut<NextState<DataState>>,
+    mut toasts: EventWriter<ToastEvent>,
+) {
+    let Some(handle) = handle else {
+        return;
+    };
+
+    let loading = *state.get() == DataState::Loading;
+
+    // The asset server puts the asset back when it's reloaded.
+    let Some(KilterDataAsset {
+        data,
+        from_database,
+    }) = assets.remove(&handle.0)
+    else {
+        if loading {
+            if let LoadState::Failed(e) = asset_server.load_state(&handle.0) {
+                warn!("Failed to load climb data. {}", e);
+                toasts.write(ToastEvent("Failed to load climb data".to_string()));
+                next_state.set(DataState::Ready);
+            }
+        }
+        return;
+    };
+
+    let local: Vec<_> = kilter
+        .climbs()
+        .values()
+        .filter(|c| c.origin.is_local())
+        .cloned()
+        .collect();
+
+    *kilter = data;
+    for climb in local {
+        kilter.insert_climb(climb);
+    }
+    for update in &applied.0 {
+        if let Err(e) = kilter.json_update_tables(update) {
+            warn!("Failed to apply update again. {}", e);
+        }
+    }
+
+    if loading {
+        if from_database {
+            commands.insert_resource(SnapshotPending);
+        }
+        next_state.set(DataState::Ready);
+    } else {
+        info!("Reloaded climb data");
+        toasts.write(ToastEvent("Reloaded climb data".to_string()));
+    }
+}
+
+/// Saves data loaded from a database as a snapshot next to it, which loads
+/// much faster on the next run.
+#[cfg(not(any(target_arch = "wasm32", target_os = "android", target_os = "ios")))]
+fn write_snapshot(
+    mut commands: Commands,
+    pending: Option<Res<SnapshotPending>>,
+    source: Option<Res<KilterDataSource>>,
+    kilter: Res<KilterData>,
+    asset_server: Res<AssetServer>,
+) {
+    use crate::kilter_data::SNAPSHOT_PATH;
+    use bevy::tasks::IoTaskPool;
+
+    let (Some(_), Some(source)) = (pending, source) else {
+        return;
+    };
+    commands.remove_resource::<SnapshotPending>();
+
+    let bytes = match kilter.to_snapshot() {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            warn!("Failed to write snapshot. {}", e);
+            return;
+        }
+    };
+
+    let db_path = AssetPath::parse(&source.0).into_owned();
+    let path = sibling(db_path.path(), SNAPSHOT_PATH);
+    let asset_server = asset_server.clone();
+
+    IoTaskPool::get()
+        .spawn(async move {
+            let written = match asset_server.get_source(db_path.source()) {
+                Ok(source) => match source.writer() {
+                    Ok(writer) => writer.write_bytes(&path, &bytes).await.map_err(Into::into),
+                    Err(e) => Err(Box::<dyn std::error::Error + Send + Sync>::from(e)),
+                },
+                Err(e) => Err(e.into()),
+            };
+
+            if let Err(e) = written {
+                warn!("Failed to write snapshot. {}", e);
+            }
+        })
+        .detach();
+}
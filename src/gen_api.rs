@@ -3,8 +3,8 @@ use bevy_http_client::prelude::*;
 use serde_derive::Deserialize;
 
 use crate::{
-    kilter_board::SelectedClimb,
-    kilter_data::{Climb, KilterData},
+    kilter_board::{ActiveLayout, SelectedClimb},
+    kilter_data::{Climb, ClimbOrigin, Frames, KilterData},
 };
 
 pub struct GenApiPlugin;
@@ -54,23 +54,38 @@ fn handle_response(
     mut ev_response: EventReader<TypedResponse<GeneratedClimbs>>,
     mut kilter: ResMut<KilterData>,
     mut selected: ResMut<SelectedClimb>,
+    active: Res<ActiveLayout>,
 ) {
     for response in ev_response.read() {
+        let mut added = 0;
+
         for generated_climb in &**response {
-            kilter.climbs.insert(
-                generated_climb.uuid.clone(),
-                Climb {
-                    uuid: generated_climb.uuid.clone(),
-                    setter_username: "API".to_string(),
-                    name: generated_climb.name.clone(),
-                    frames: generated_climb.frames.clone(),
-                    description: generated_climb.description.clone(),
-                    angle: generated_climb.angle,
-                    ..default()
-                },
-            );
+            let frames = match Frames::parse(&generated_climb.frames) {
+                Ok(frames) => frames,
+                Err(e) => {
+                    warn!("Generated climb {}: {}", generated_climb.uuid, e);
+                    continue;
+                }
+            };
+
+            kilter.insert_climb(Climb {
+                uuid: generated_climb.uuid.clone(),
+                setter_username: "API".to_string(),
+                name: generated_climb.name.clone(),
+                frames_count: frames.len() as u32,
+                frames,
+                description: generated_climb.description.clone(),
+                angle: generated_climb.angle,
+                layout_id: active.layout_id,
+                origin: ClimbOrigin::Generated,
+                ..default()
+            });
+
+            added += 1;
         }
 
-        selected.0 = kilter.climbs.len() - response.len();
+        if added > 0 {
+            selected.0 = kilter.climbs().len() - added;
+        }
     }
 }
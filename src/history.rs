@@ -0,0 +1,490 @@
+use bevy::{
+    platform::collections::{HashMap, HashSet},
+    prelude::*,
+};
+use bevy_simple_text_input::TextInputInactive;
+
+use crate::{
+    kilter_board::SelectedClimb,
+    kilter_data::{Climb, ClimbOrigin, Frame, KilterData},
+    ui::toast_panel::ToastEvent,
+};
+
+/// Applies edits to climbs and keeps a separate undo/redo history for each
+/// climb.
+pub struct HistoryPlugin;
+
+impl Plugin for HistoryPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<EditEvent>()
+            .add_event::<HistoryEvent>()
+            .init_resource::<EditHistory>()
+            .add_systems(Update, (history_keys, apply_edits).chain());
+    }
+}
+
+/// A reversible change to the climbs.
+#[derive(Debug, Clone)]
+pub enum Edit {
+    /// Adds a hold, changes its role or removes it.
+    SetHold {
+        uuid: String,
+        frame: usize,
+        placement_id: u32,
+        from: Option<u32>,
+        to: Option<u32>,
+        /// The climb's origin before the edit, to restore when it's undone.
+        origin: ClimbOrigin,
+    },
+    /// Moves a hold to another placement, keeping its role.
+    MoveHold {
+        uuid: String,
+        frame: usize,
+        from: u32,
+        to: u32,
+        role_id: u32,
+        /// The climb's origin before the edit, to restore when it's undone.
+        origin: ClimbOrigin,
+    },
+    /// Removes every hold from a frame.
+    Clear {
+        uuid: String,
+        frame: usize,
+        holds: Frame,
+        /// The climb's origin before the edit, to restore when it's undone.
+        origin: ClimbOrigin,
+    },
+    /// Adds new climbs, e.g. pasted or mirrored ones, while `from` was
+    /// selected.
+    AddClimbs {
+        from: String,
+        climbs: Vec<Climb>,
+        name: &'static str,
+    },
+    /// Changes a climb's name, description or other details.
+    Details {
+        uuid: String,
+        before: ClimbDetails,
+        after: ClimbDetails,
+        /// The climb's origin before the edit, to restore when it's undone.
+        origin: ClimbOrigin,
+    },
+}
+
+/// Details of a climb changed by an edit. Fields that weren't changed are
+/// `None`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ClimbDetails {
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub setter_username: Option<String>,
+    pub angle: Option<Option<u32>>,
+    pub is_draft: Option<bool>,
+    pub is_listed: Option<bool>,
+}
+impl ClimbDetails {
+    /// The details that differ between `before` and `after`, as they are in
+    /// each.
+    pub fn diff(before: &Climb, after: &Climb) -> (Self, Self) {
+        fn field<T: Clone + PartialEq>(
+            before: &T,
+            after: &T,
+            details: (&mut Option<T>, &mut Option<T>),
+        ) {
+            if before != after {
+                *details.0 = Some(before.clone());
+                *details.1 = Some(after.clone());
+            }
+        }
+
+        let (mut b, mut a) = (Self::default(), Self::default());
+        field(&before.name, &after.name, (&mut b.name, &mut a.name));
+        field(
+            &before.description,
+            &after.description,
+            (&mut b.description, &mut a.description),
+        );
+        field(
+            &before.setter_username,
+            &after.setter_username,
+            (&mut b.setter_username, &mut a.setter_username),
+        );
+        field(&before.angle, &after.angle, (&mut b.angle, &mut a.angle));
+        field(
+            &before.is_draft,
+            &after.is_draft,
+            (&mut b.is_draft, &mut a.is_draft),
+        );
+        field(
+            &before.is_listed,
+            &after.is_listed,
+            (&mut b.is_listed, &mut a.is_listed),
+        );
+
+        (b, a)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    fn apply_to(&self, climb: &mut Climb) {
+        if let Some(name) = &self.name {
+            climb.name.clone_from(name);
+        }
+        if let Some(description) = &self.description {
+            climb.description.clone_from(description);
+        }
+        if let Some(setter_username) = &self.setter_username {
+            climb.setter_username.clone_from(setter_username);
+        }
+        if let Some(angle) = self.angle {
+            climb.angle = angle;
+        }
+        if let Some(is_draft) = self.is_draft {
+            climb.is_draft = is_draft;
+        }
+        if let Some(is_listed) = self.is_listed {
+            climb.is_listed = is_listed;
+        }
+    }
+}
+impl Edit {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::SetHold { from: None, .. } => "add hold",
+            Self::SetHold { to: None, .. } => "remove hold",
+            Self::SetHold { .. } => "change role",
+            Self::MoveHold { .. } => "move hold",
+            Self::Clear { .. } => "clear",
+            Self::AddClimbs { name, .. } => name,
+            Self::Details { .. } => "edit details",
+        }
+    }
+
+    /// The climb whose history this edit belongs to after being applied, or
+    /// after being reverted.
+    ///
+    /// Undoing adding climbs returns to the climb that was selected before,
+    /// so they can be added again from there.
+    fn scope(&self, applied: bool) -> &str {
+        match self {
+            Self::SetHold { uuid, .. }
+            | Self::MoveHold { uuid, .. }
+            | Self::Clear { uuid, .. }
+            | Self::Details { uuid, .. } => uuid,
+            Self::AddClimbs { from, .. } if !applied => from,
+            Self::AddClimbs { climbs, .. } => climbs.first().map(|c| c.uuid.as_str()).unwrap_or(""),
+        }
+    }
+
+    /// Applies the edit, or reverts it if `forward` is false.
+    fn apply(&self, kilter: &mut KilterData, selected: &mut SelectedClimb, forward: bool) {
+        match self {
+            Self::SetHold {
+                uuid,
+                frame,
+                placement_id,
+                from,
+                to,
+                origin,
+            } => edit_frame(kilter, uuid, *frame, *origin, forward, |f| {
+                f.set_role(*placement_id, if forward { *to } else { *from })
+            }),
+            Self::MoveHold {
+                uuid,
+                frame,
+                from,
+                to,
+                role_id,
+                origin,
+            } => edit_frame(kilter, uuid, *frame, *origin, forward, |f| {
+                let (from, to) = if forward { (from, to) } else { (to, from) };
+                f.set_role(*from, None);
+                f.set_role(*to, Some(*role_id));
+            }),
+            Self::Clear {
+                uuid,
+                frame,
+                holds,
+                origin,
+            } => edit_frame(kilter, uuid, *frame, *origin, forward, |f| {
+                *f = if forward {
+                    Frame::default()
+                } else {
+                    holds.clone()
+                }
+            }),
+            Self::AddClimbs { from, climbs, .. } => {
+                if forward {
+                    for climb in climbs {
+                        kilter.insert_climb(climb.clone());
+                    }
+                    if let Some(idx) = climbs.first().and_then(|c| kilter.climb_index(&c.uuid)) {
+                        selected.0 = idx;
+                    }
+                } else {
+                    let uuids: HashSet<_> = climbs.iter().map(|c| c.uuid.clone()).collect();
+                    kilter.remove_climbs(&uuids);
+                    selected.0 = kilter.climb_index(from).unwrap_or(0);
+                }
+            }
+            Self::Details {
+                uuid,
+                before,
+                after,
+                origin,
+            } => {
+                let Some(mut climb) = kilter.climb(uuid).cloned() else {
+                    warn!("Can't edit missing climb {}", uuid);
+                    return;
+                };
+
+                if forward {
+                    after.apply_to(&mut climb);
+                } else {
+                    before.apply_to(&mut climb);
+                }
+                set_origin(&mut climb, *origin, forward);
+
+                kilter.insert_climb(climb);
+            }
+        }
+    }
+}
+
+/// Marks a climb as edited when an edit is applied, or restores its origin
+/// from before the edit when it's reverted.
+fn set_origin(climb: &mut Climb, origin: ClimbOrigin, forward: bool) {
+    if forward {
+        climb.mark_edited();
+    } else {
+        climb.origin = origin;
+    }
+}
+
+fn edit_frame(
+    kilter: &mut KilterData,
+    uuid: &str,
+    index: usize,
+    origin: ClimbOrigin,
+    forward: bool,
+    edit: impl FnOnce(&mut Frame),
+) {
+    let Some(mut climb) = kilter.climb(uuid).cloned() else {
+        warn!("Can't edit missing climb {}", uuid);
+        return;
+    };
+
+    let mut frame = climb.frames.get(index).cloned().unwrap_or_default();
+    edit(&mut frame);
+    climb.frames.set(index, frame);
+    climb.frames_count = climb.frames.len() as u32;
+    set_origin(&mut climb, origin, forward);
+
+    kilter.insert_climb(climb);
+}
+
+/// Applies an edit and records it in the history.
+#[derive(Event)]
+pub struct EditEvent(pub Edit);
+
+/// Undoes or redoes the most recent edit to the selected climb.
+#[derive(Event, Clone, Copy, PartialEq, Eq)]
+pub enum HistoryEvent {
+    Undo,
+    Redo,
+}
+
+#[derive(Resource, Default)]
+struct EditHistory {
+    undo: HashMap<String, Vec<Edit>>,
+    redo: HashMap<String, Vec<Edit>>,
+}
+
+fn history_keys(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    text_inputs: Query<&TextInputInactive>,
+    mut events: EventWriter<HistoryEvent>,
+) {
+    // Keys belong to the text input being typed in.
+    if text_inputs.iter().any(|inactive| !inactive.0) {
+        return;
+    }
+
+    let ctrl_pressed = keyboard_input.any_pressed([
+        KeyCode::ControlLeft,
+        KeyCode::SuperLeft,
+        KeyCode::ControlRight,
+        KeyCode::SuperRight,
+    ]);
+    let shift_pressed = keyboard_input.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+
+    if !ctrl_pressed {
+        return;
+    }
+
+    if keyboard_input.just_pressed(KeyCode::KeyZ) {
+        events.write(if shift_pressed {
+            HistoryEvent::Redo
+        } else {
+            HistoryEvent::Undo
+        });
+    } else if keyboard_input.just_pressed(KeyCode::KeyY) {
+        events.write(HistoryEvent::Redo);
+    }
+}
+
+fn apply_edits(
+    mut edit_events: EventReader<EditEvent>,
+    mut history_events: EventReader<HistoryEvent>,
+    mut history: ResMut<EditHistory>,
+    mut kilter: ResMut<KilterData>,
+    mut selected: ResMut<SelectedClimb>,
+    mut toasts: EventWriter<ToastEvent>,
+) {
+    for EditEvent(edit) in edit_events.read() {
+        edit.apply(&mut kilter, &mut selected, true);
+
+        // A new edit replaces anything that could have been redone.
+        history.redo.remove(edit.scope(false));
+        history
+            .undo
+            .entry(edit.scope(true).to_string())
+            .or_default()
+            .push(edit.clone());
+    }
+
+    for event in history_events.read() {
+        let Some((uuid, _)) = kilter.climb_at(selected.0) else {
+            continue;
+        };
+        let uuid = uuid.clone();
+
+        let (stack, verb) = match event {
+            HistoryEvent::Undo => (&mut history.undo, "undo"),
+            HistoryEvent::Redo => (&mut history.redo, "redo"),
+        };
+
+        let Some(edit) = stack.get_mut(&uuid).and_then(Vec::pop) else {
+            toasts.write(ToastEvent(format!("Nothing to {verb}")));
+            continue;
+        };
+
+        let forward = *event == HistoryEvent::Redo;
+        edit.apply(&mut kilter, &mut selected, forward);
+        toasts.write(ToastEvent(format!(
+            "{} {}",
+            if forward { "Redid" } else { "Undid" },
+            edit.name()
+        )));
+
+        let stack = if forward {
+            &mut history.undo
+        } else {
+            &mut history.redo
+        };
+        stack
+            .entry(edit.scope(forward).to_string())
+            .or_default()
+            .push(edit);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kilter_data::Frames;
+
+    #[test]
+    fn details_hold_only_changed_fields() {
+        let before = Climb {
+            name: "Old".to_string(),
+            description: "Same".to_string(),
+            angle: Some(40),
+            ..default()
+        };
+        let after = Climb {
+            name: "New".to_string(),
+            angle: None,
+            ..before.clone()
+        };
+
+        let (b, a) = ClimbDetails::diff(&before, &after);
+        assert_eq!(
+            a,
+            ClimbDetails {
+                name: Some("New".to_string()),
+                angle: Some(None),
+                ..default()
+            }
+        );
+
+        let mut climb = after.clone();
+        b.apply_to(&mut climb);
+        assert_eq!(climb.name, before.name);
+        assert_eq!(climb.angle, before.angle);
+        assert_eq!(climb.description, before.description);
+
+        assert!(ClimbDetails::diff(&before, &before).1.is_empty());
+    }
+
+    #[test]
+    fn undoing_hold_edits_restores_frames_and_origin() {
+        let mut kilter = KilterData::default();
+        kilter.insert_climb(Climb {
+            uuid: "a".to_string(),
+            frames: "p1r12p2r13".parse().unwrap(),
+            ..default()
+        });
+        let mut selected = SelectedClimb(0);
+
+        let edits = [
+            Edit::SetHold {
+                uuid: "a".to_string(),
+                frame: 0,
+                placement_id: 3,
+                from: None,
+                to: Some(14),
+                origin: ClimbOrigin::Database,
+            },
+            Edit::MoveHold {
+                uuid: "a".to_string(),
+                frame: 0,
+                from: 1,
+                to: 4,
+                role_id: 12,
+                origin: ClimbOrigin::Edited,
+            },
+            Edit::Clear {
+                uuid: "a".to_string(),
+                frame: 0,
+                holds: "p2r13p3r14p4r12"
+                    .parse::<Frames>()
+                    .unwrap()
+                    .get(0)
+                    .cloned()
+                    .unwrap(),
+                origin: ClimbOrigin::Edited,
+            },
+        ];
+
+        let climb = |kilter: &KilterData| kilter.climb("a").cloned().unwrap();
+
+        for edit in &edits {
+            edit.apply(&mut kilter, &mut selected, true);
+            assert_eq!(climb(&kilter).origin, ClimbOrigin::Edited);
+        }
+        assert!(climb(&kilter).frames.get(0).unwrap().is_empty());
+
+        for edit in edits.iter().rev() {
+            edit.apply(&mut kilter, &mut selected, false);
+        }
+        // Moving a hold back adds it after the others, so compare roles.
+        let climb = climb(&kilter);
+        let frame = climb.frames.get(0).unwrap();
+        let roles: Vec<_> = (1..=4).map(|p| frame.role_of(p)).collect();
+        assert_eq!(roles, [Some(12), Some(13), None, None]);
+        assert_eq!(climb.origin, ClimbOrigin::Database);
+    }
+}
@@ -2,14 +2,14 @@ use std::{f32::consts::PI, time::Duration};
 
 use bevy::prelude::*;
 
-use crate::kilter_board::BOARD_HEIGHT;
+use crate::kilter_board::{self, BoardPivot};
 
 pub struct HumanPlugin;
 
 impl Plugin for HumanPlugin {
     fn build(&self, app: &mut App) {
         app.init_resource::<HumanAssets>();
-        app.add_systems(Startup, setup);
+        app.add_systems(Startup, setup.after(kilter_board::setup_scene));
         app.add_systems(Update, setup_scene_once_loaded);
     }
 }
@@ -71,13 +71,19 @@ fn setup_scene_once_loaded(
     }
 }
 
-fn setup(mut commands: Commands, assets: Res<HumanAssets>) {
+/// Stands the human against the board so that they tilt along with it.
+fn setup(
+    mut commands: Commands,
+    assets: Res<HumanAssets>,
+    pivot: Single<Entity, With<BoardPivot>>,
+) {
     commands.spawn((
         SceneRoot(assets.scene.clone()),
         Transform {
             rotation: Quat::from_euler(EulerRot::XZY, -PI / 2., 3.6, 0.0),
             scale: Vec3::splat(1.76 / 2.),
-            translation: Vec3::new(1.9, -BOARD_HEIGHT / 2., 1.6),
+            translation: Vec3::new(1.9, 0., 0.3),
         },
+        ChildOf(*pivot),
     ));
 }
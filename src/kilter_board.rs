@@ -1,16 +1,66 @@
-use bevy::{pbr::CascadeShadowConfigBuilder, prelude::*};
-use combine::EasyParser;
+use bevy::{pbr::CascadeShadowConfigBuilder, platform::collections::HashSet, prelude::*};
 
 use crate::{
-    kilter_data::{placements_and_roles, KilterData},
+    data_loading::DataState,
+    kilter_data::{Climb, FramePlacement, KilterData, ProductSize},
     placement_indicator::PlacementIndicator,
 };
 
 #[derive(Resource, Default)]
 pub struct SelectedClimb(pub usize);
 
+/// Which frame of a multi-frame climb is shown, and whether frames are
+/// advancing on their own.
+#[derive(Resource, Default)]
+pub struct FramePlayback {
+    pub frame: usize,
+    pub playing: bool,
+    timer: Timer,
+}
+impl FramePlayback {
+    /// Moves `delta` frames forward or back, wrapping around `frames_count`.
+    pub fn step(&mut self, delta: isize, frames_count: usize) {
+        if frames_count == 0 {
+            return;
+        }
+
+        self.frame = (self.frame as isize + delta).rem_euclid(frames_count as isize) as usize;
+        self.timer.reset();
+    }
+}
+
 #[derive(Component)]
 pub struct Board;
+impl Board {
+    /// Converts a point in world space, e.g. a pointer hit, to a position
+    /// relative to the board's center.
+    pub fn local_position(board: &GlobalTransform, point: Vec3) -> Vec2 {
+        board.affine().inverse().transform_point3(point).truncate()
+    }
+}
+
+/// An image of an installed set's holds, layered over a bare board.
+#[derive(Component)]
+struct SetOverlay;
+
+/// A beam of the frame around the board, or the kickboard under it. These are
+/// rebuilt whenever the board's size changes.
+#[derive(Component)]
+struct BoardFrame;
+
+/// Tilts the board, and everything attached to it, around its bottom edge.
+#[derive(Component, Default)]
+#[require(Transform, Visibility)]
+pub struct BoardPivot {
+    /// The angle in degrees the board is tilted to, which follows
+    /// [`BoardAngle`].
+    pub angle: f32,
+}
+impl BoardPivot {
+    pub fn rotation(&self) -> Quat {
+        Quat::from_rotation_x(self.angle.to_radians())
+    }
+}
 
 #[derive(Event)]
 pub enum ChangeClimbEvent {
@@ -51,10 +101,129 @@ impl BoardAngle {
     }
 }
 
-pub const BOARD_HEIGHT: f32 = 3.9;
+/// The layout, and optionally the product size, shown on the board.
+#[derive(Resource)]
+pub struct ActiveLayout {
+    pub layout_id: u32,
+    /// When `None`, every placement on the layout is shown.
+    pub product_size_id: Option<u32>,
+    /// Sets that aren't installed on the wall. Climbs using them are hidden.
+    pub missing_sets: HashSet<u32>,
+}
+impl Default for ActiveLayout {
+    fn default() -> Self {
+        Self {
+            layout_id: 1,
+            product_size_id: None,
+            missing_sets: HashSet::default(),
+        }
+    }
+}
+impl ActiveLayout {
+    pub fn shows_climb(&self, kilter: &KilterData, climb: &Climb) -> bool {
+        self.fits_climb(kilter, climb) && !kilter.climb_uses_sets(&climb.uuid, &self.missing_sets)
+    }
+
+    /// Whether `climb` is on the layout and fits on the product size, even if
+    /// it uses sets that are missing.
+    pub fn fits_climb(&self, kilter: &KilterData, climb: &Climb) -> bool {
+        kilter.climb_on(climb, self.layout_id, self.product_size_id)
+    }
+
+    pub fn shows_placement(&self, kilter: &KilterData, placement_id: u32) -> bool {
+        kilter.placement_on(placement_id, self.layout_id, self.product_size_id)
+    }
+
+    /// The product the active layout belongs to.
+    pub fn product_id(&self, kilter: &KilterData) -> Option<u32> {
+        kilter.layout_product_id(self.layout_id)
+    }
+
+    /// The product size the board is built from: the selected one, or else
+    /// the largest listed size of the layout's product.
+    pub fn board_size<'a>(&self, kilter: &'a KilterData) -> Option<&'a ProductSize> {
+        if let Some(size) = self
+            .product_size_id
+            .and_then(|id| kilter.product_sizes.get(&id))
+        {
+            return Some(size);
+        }
+
+        kilter
+            .sorted_product_sizes(self.product_id(kilter)?)
+            .into_iter()
+            .filter(|s| s.is_listed)
+            .max_by_key(|s| (s.edge_right - s.edge_left) * (s.edge_top - s.edge_bottom))
+    }
+
+    /// The edges of the board, in hole coordinates.
+    pub fn board_edges(&self, kilter: &KilterData) -> IRect {
+        self.board_size(kilter)
+            .map(|s| IRect::new(s.edge_left, s.edge_bottom, s.edge_right, s.edge_top))
+            .unwrap_or(DEFAULT_BOARD_EDGES)
+    }
+
+    /// The image to use as the board's texture, covering the board's edges, or
+    /// `None` when the board is made up of [`ActiveLayout::set_images`].
+    ///
+    /// Product size images come from the Kilter Board app and must be copied
+    /// into `assets`.
+    pub fn board_image(&self, kilter: &KilterData) -> Option<String> {
+        let Some(size) = self.board_size(kilter) else {
+            return Some(DEFAULT_BOARD_IMAGE.to_string());
+        };
+
+        // The size's image shows every set, even those that aren't installed.
+        if kilter.has_set_images(size.id, self.layout_id) {
+            return None;
+        }
+
+        Some(
+            size.image_filename
+                .clone()
+                .unwrap_or_else(|| DEFAULT_BOARD_IMAGE.to_string()),
+        )
+    }
+
+    /// Images of the holds of each installed set on the board, drawn over a
+    /// bare board when [`ActiveLayout::board_image`] is `None`.
+    ///
+    /// Set images come from the Kilter Board app and must be copied into
+    /// `assets`.
+    pub fn set_images(&self, kilter: &KilterData) -> Vec<String> {
+        let Some(size) = self.board_size(kilter) else {
+            return vec![];
+        };
+
+        kilter
+            .layout_set_ids(self.layout_id)
+            .iter()
+            .filter(|set_id| !self.missing_sets.contains(*set_id))
+            .filter_map(|&set_id| kilter.set_image(size.id, self.layout_id, set_id))
+            .map(ToString::to_string)
+            .collect()
+    }
+}
+
+pub const DEFAULT_BOARD_IMAGE: &str = "original-16x12.png";
+/// The edges of the board in [`DEFAULT_BOARD_IMAGE`], in hole coordinates.
+pub const DEFAULT_BOARD_EDGES: IRect = IRect {
+    min: IVec2::new(-24, 0),
+    max: IVec2::new(168, 156),
+};
+/// The color of a board without holds, under the images of each set.
+const BARE_BOARD_COLOR: Color = Color::srgb(0.78, 0.68, 0.55);
+/// The width and depth of the beams framing the board, in world units.
+const FRAME_WIDTH: f32 = 0.1;
+const FRAME_DEPTH: f32 = 0.2;
+/// The height of the kickboard, the upright panel the board is hinged on top
+/// of, in world units.
+const KICKBOARD_HEIGHT: f32 = 0.3;
 pub const MIN_BOARD_ANGLE: u32 = 0;
 pub const MAX_BOARD_ANGLE: u32 = 70;
 pub const BOARD_ANGLE_STEP: u32 = 5;
+/// How quickly the board tilts to a new angle.
+const BOARD_TILT_DECAY_RATE: f32 = 8.0;
 
 pub struct KilterBoardPlugin;
 
@@ -65,21 +234,38 @@ impl Plugin for KilterBoardPlugin {
             (
                 show_climb.before(crate::placement_indicator::update),
                 prev_next_climb,
+                keep_selected_climb
+                    .before(change_climb)
+                    .before(select_layout_climb)
+                    .before(reset_playback)
+                    .before(show_climb),
                 change_climb,
-            ),
+                select_layout_climb.before(show_climb),
+                build_board,
+                update_set_overlays.after(build_board),
+                reset_playback
+                    .after(change_climb)
+                    .after(select_layout_climb)
+                    .before(show_climb),
+                advance_playback.before(show_climb),
+            )
+                .run_if(in_state(DataState::Ready)),
         )
+        .add_systems(Update, tilt_board)
         .add_systems(Startup, setup_scene)
         .add_event::<ChangeClimbEvent>()
         .init_resource::<BoardAngle>()
+        .init_resource::<ActiveLayout>()
         .init_resource::<SelectedClimb>()
+        .init_resource::<FramePlayback>()
         .init_resource::<KilterSettings>()
         .register_type::<KilterSettings>();
     }
 }
 
-fn setup_scene(
+pub fn setup_scene(
     mut commands: Commands,
-    asset_server: Res<AssetServer>,
+    angle: Res<BoardAngle>,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<StandardMaterial>>,
 ) {
@@ -103,26 +289,29 @@ fn setup_scene(
         .build(),
     ));
 
-    let board_width = 1477. / 1200. * BOARD_HEIGHT;
+    let pivot = BoardPivot {
+        angle: angle.0 as f32,
+    };
+    // The board tilts about its bottom edge, on top of the kickboard.
+    let pivot = commands
+        .spawn((
+            Transform::from_xyz(0., KICKBOARD_HEIGHT, 0.).with_rotation(pivot.rotation()),
+            pivot,
+        ))
+        .id();
 
+    // Sized and textured by `build_board`.
     commands.spawn((
-        Mesh3d(meshes.add(Rectangle::new(board_width, BOARD_HEIGHT))),
-        MeshMaterial3d(materials.add(StandardMaterial {
-            base_color_texture: Some(asset_server.load("original-16x12.png")),
-            ..default()
-        })),
+        Mesh3d::default(),
+        MeshMaterial3d(materials.add(StandardMaterial::default())),
         Board,
+        ChildOf(pivot),
     ));
 
-    // TODO: adjust scene so the floor is at y=0
     commands.spawn((
         Mesh3d(meshes.add(Circle::new(3.0))),
         MeshMaterial3d(materials.add(Color::WHITE)),
-        Transform {
-            translation: Vec3::new(0., -BOARD_HEIGHT / 2., 0.),
-            rotation: Quat::from_rotation_x(-std::f32::consts::FRAC_PI_2),
-            ..default()
-        },
+        Transform::from_rotation(Quat::from_rotation_x(-std::f32::consts::FRAC_PI_2)),
     ));
 }
 
@@ -138,23 +327,38 @@ fn prev_next_climb(keys: Res<ButtonInput<KeyCode>>, mut writer: EventWriter<Chan
 fn change_climb(
     mut selected: ResMut<SelectedClimb>,
     kilter: Res<KilterData>,
+    active: Res<ActiveLayout>,
     mut reader: EventReader<ChangeClimbEvent>,
 ) {
+    let len = kilter.climbs().len();
+
+    let shown = |idx: usize| {
+        kilter
+            .climb_at(idx)
+            .is_some_and(|(_, climb)| active.shows_climb(&kilter, climb))
+    };
+
     for event in reader.read() {
+        if len == 0 {
+            continue;
+        }
+
         match event {
             ChangeClimbEvent::Prev => {
-                selected.0 = if selected.0 == 0 {
-                    kilter.climbs.len() - 1
-                } else {
-                    selected.0 - 1
-                };
+                if let Some(idx) = (1..=len)
+                    .map(|i| (selected.0 + len - i) % len)
+                    .find(|idx| shown(*idx))
+                {
+                    selected.0 = idx;
+                }
             }
             ChangeClimbEvent::Next => {
-                selected.0 = if selected.0 + 1 >= kilter.climbs.len() {
-                    0
-                } else {
-                    selected.0 + 1
-                };
+                if let Some(idx) = (1..=len)
+                    .map(|i| (selected.0 + i) % len)
+                    .find(|idx| shown(*idx))
+                {
+                    selected.0 = idx;
+                }
             }
             ChangeClimbEvent::SelectByIndex(id) => {
                 selected.0 = *id;
@@ -163,15 +367,302 @@ fn change_climb(
     }
 }
 
+/// Keeps the same climb selected when removing climbs shifts the indices of
+/// those after them, or selects the climb that took its place if it was
+/// removed.
+fn keep_selected_climb(
+    mut selected: ResMut<SelectedClimb>,
+    kilter: Res<KilterData>,
+    mut selected_uuid: Local<Option<String>>,
+) {
+    let uuid_at = |idx: usize| kilter.climb_at(idx).map(|(uuid, _)| uuid.clone());
+
+    if selected.is_changed() || selected_uuid.is_none() {
+        *selected_uuid = uuid_at(selected.0);
+        return;
+    }
+
+    if !kilter.is_changed() {
+        return;
+    }
+
+    let Some(uuid) = selected_uuid.as_deref() else {
+        return;
+    };
+
+    match kilter.climb_index(uuid) {
+        // Still the same climb, so nothing else needs to know.
+        Some(idx) => selected.bypass_change_detection().0 = idx,
+        None => {
+            selected.0 = selected.0.min(kilter.climbs().len().saturating_sub(1));
+            *selected_uuid = uuid_at(selected.0);
+        }
+    }
+}
+
+/// Selects the first climb on the active layout if the selected climb isn't on
+/// it.
+fn select_layout_climb(
+    mut selected: ResMut<SelectedClimb>,
+    kilter: Res<KilterData>,
+    active: Res<ActiveLayout>,
+) {
+    if !active.is_changed() {
+        return;
+    }
+
+    if kilter
+        .climb_at(selected.0)
+        .is_some_and(|(_, climb)| active.shows_climb(&kilter, climb))
+    {
+        return;
+    }
+
+    if let Some(idx) = kilter
+        .climbs()
+        .values()
+        .position(|climb| active.shows_climb(&kilter, climb))
+    {
+        selected.0 = idx;
+    }
+}
+
+/// Sizes the board to the edges of the active product size, with its bottom
+/// edge on top of the kickboard, and frames it.
+///
+/// Also moves [`KilterSettings::offset`] so that holes line up with the
+/// board's image.
+fn build_board(
+    mut commands: Commands,
+    active: Res<ActiveLayout>,
+    kilter: Res<KilterData>,
+    mut settings: ResMut<KilterSettings>,
+    asset_server: Res<AssetServer>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut boards: Query<
+        (
+            &mut Mesh3d,
+            &MeshMaterial3d<StandardMaterial>,
+            &mut Transform,
+        ),
+        With<Board>,
+    >,
+    pivots: Query<Entity, With<BoardPivot>>,
+    frames: Query<Entity, With<BoardFrame>>,
+    mut built: Local<Option<(IRect, Option<String>, f32)>>,
+) {
+    if !active.is_changed() && !kilter.is_changed() && !settings.is_changed() {
+        return;
+    }
+
+    let edges = active.board_edges(&kilter);
+    let image = active.board_image(&kilter);
+
+    let offset = -edges.as_rect().center() * settings.scale;
+    if settings.offset != offset {
+        settings.offset = offset;
+    }
+
+    let key = (edges, image, settings.scale);
+    if built.as_ref() == Some(&key) {
+        return;
+    }
+    let (edges, image, scale) = key.clone();
+    *built = Some(key);
+
+    let Ok(pivot) = pivots.single() else {
+        return;
+    };
+    let Ok((mut mesh, material, mut transform)) = boards.single_mut() else {
+        return;
+    };
+
+    let size = edges.size().as_vec2() * scale;
+
+    mesh.0 = meshes.add(Rectangle::from_size(size));
+    transform.translation = Vec3::new(0., size.y / 2., 0.);
+
+    if let Some(material) = materials.get_mut(material) {
+        material.base_color = if image.is_some() {
+            Color::WHITE
+        } else {
+            BARE_BOARD_COLOR
+        };
+        material.base_color_texture = image.map(|image| asset_server.load(image));
+    }
+
+    for entity in &frames {
+        commands.entity(entity).despawn();
+    }
+
+    let frame_material = materials.add(Color::srgb(0.15, 0.15, 0.15));
+    let frame_width = size.x + FRAME_WIDTH * 2.;
+
+    // Beams are behind the board so that they don't cover any holds.
+    let beams = [
+        // Sides
+        (
+            Vec2::new(FRAME_WIDTH, size.y),
+            Vec2::new(-(size.x + FRAME_WIDTH) / 2., size.y / 2.),
+        ),
+        (
+            Vec2::new(FRAME_WIDTH, size.y),
+            Vec2::new((size.x + FRAME_WIDTH) / 2., size.y / 2.),
+        ),
+        // Top
+        (
+            Vec2::new(frame_width, FRAME_WIDTH),
+            Vec2::new(0., size.y + FRAME_WIDTH / 2.),
+        ),
+    ];
+
+    for (beam_size, center) in beams {
+        commands.spawn((
+            Mesh3d(meshes.add(Cuboid::new(beam_size.x, beam_size.y, FRAME_DEPTH))),
+            MeshMaterial3d(frame_material.clone()),
+            Transform::from_translation(center.extend(-FRAME_DEPTH / 2. - 0.001)),
+            BoardFrame,
+            ChildOf(pivot),
+        ));
+    }
+
+    // The kickboard stays upright under the board's bottom edge as it tilts,
+    // its face flush with the board's when the board is vertical.
+    commands.spawn((
+        Mesh3d(meshes.add(Cuboid::new(frame_width, KICKBOARD_HEIGHT, FRAME_DEPTH))),
+        MeshMaterial3d(materials.add(BARE_BOARD_COLOR)),
+        Transform::from_xyz(0., KICKBOARD_HEIGHT / 2., -FRAME_DEPTH / 2.),
+        BoardFrame,
+    ));
+}
+
+/// Layers the holds of each installed set over the board, when the product
+/// size has images of them.
+fn update_set_overlays(
+    mut commands: Commands,
+    active: Res<ActiveLayout>,
+    kilter: Res<KilterData>,
+    asset_server: Res<AssetServer>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    boards: Query<(Entity, Ref<Mesh3d>), With<Board>>,
+    overlays: Query<Entity, With<SetOverlay>>,
+    mut built: Local<Vec<String>>,
+) {
+    let Ok((board, mesh)) = boards.single() else {
+        return;
+    };
+
+    if !active.is_changed() && !kilter.is_changed() && !mesh.is_changed() {
+        return;
+    }
+
+    let images = active.set_images(&kilter);
+
+    if *built == images && !mesh.is_changed() {
+        return;
+    }
+
+    for entity in &overlays {
+        commands.entity(entity).despawn();
+    }
+
+    // Overlays sit between the board and the placement indicators.
+    for (i, image) in images.iter().enumerate() {
+        commands.spawn((
+            Mesh3d(mesh.0.clone()),
+            MeshMaterial3d(materials.add(StandardMaterial {
+                base_color_texture: Some(asset_server.load(image)),
+                alpha_mode: AlphaMode::Blend,
+                ..default()
+            })),
+            Transform::from_xyz(0., 0., 0.00001 * (i + 1) as f32),
+            Pickable::IGNORE,
+            SetOverlay,
+            ChildOf(board),
+        ));
+    }
+
+    *built = images;
+}
+
+/// Eases the board towards the selected angle. Overhanging tilts the top of the
+/// board towards the camera.
+pub fn tilt_board(
+    angle: Res<BoardAngle>,
+    time: Res<Time>,
+    mut pivots: Query<(&mut BoardPivot, &mut Transform)>,
+) {
+    let target = angle.0 as f32;
+
+    for (mut pivot, mut transform) in &mut pivots {
+        if pivot.angle == target {
+            continue;
+        }
+
+        pivot
+            .angle
+            .smooth_nudge(&target, BOARD_TILT_DECAY_RATE, time.delta_secs());
+        if (pivot.angle - target).abs() < 0.01 {
+            pivot.angle = target;
+        }
+
+        transform.rotation = pivot.rotation();
+    }
+}
+
+fn reset_playback(selected: Res<SelectedClimb>, mut playback: ResMut<FramePlayback>) {
+    if !selected.is_changed() {
+        return;
+    }
+
+    playback.frame = 0;
+    playback.playing = false;
+}
+
+fn advance_playback(
+    selected: Res<SelectedClimb>,
+    kilter: Res<KilterData>,
+    mut playback: ResMut<FramePlayback>,
+    time: Res<Time>,
+) {
+    if !playback.playing {
+        return;
+    }
+
+    let Some((_, climb)) = kilter.climb_at(selected.0) else {
+        return;
+    };
+
+    // Ticking the timer alone shouldn't cause the climb to be redrawn.
+    let timer = &mut playback.bypass_change_detection().timer;
+    timer.set_duration(climb.frame_duration());
+    timer.set_mode(TimerMode::Repeating);
+    timer.tick(time.delta());
+
+    let steps = timer.times_finished_this_tick();
+    if steps > 0 {
+        playback.step(steps as isize, climb.frames.len());
+    }
+}
+
 fn show_climb(
     mut commands: Commands,
     selected: Res<SelectedClimb>,
     kilter: Res<KilterData>,
     settings: Res<KilterSettings>,
+    active: Res<ActiveLayout>,
+    playback: Res<FramePlayback>,
     indicators: Query<Entity, With<PlacementIndicator>>,
     boards: Query<Entity, With<Board>>,
 ) {
-    if !selected.is_added() && !selected.is_changed() && !settings.is_changed() {
+    if !selected.is_added()
+        && !selected.is_changed()
+        && !kilter.is_changed()
+        && !settings.is_changed()
+        && !active.is_changed()
+        && !playback.is_changed()
+    {
         return;
     }
 
@@ -181,10 +672,10 @@ fn show_climb(
 
     // Get selected or first climb
     let Some(climb) = kilter
-        .climbs
+        .climbs()
         .iter()
         .nth(selected.0)
-        .or_else(|| kilter.climbs.iter().next())
+        .or_else(|| kilter.climbs().iter().next())
         .map(|(_, climb)| climb)
     else {
         return;
@@ -194,11 +685,29 @@ fn show_climb(
         commands.entity(entity).despawn();
     }
 
-    let Ok((placements, _)) = placements_and_roles().easy_parse(climb.frames.as_str()) else {
+    // Climbs using missing sets are still shown when selected directly, and
+    // flagged in the info panel.
+    if !active.fits_climb(&kilter, climb) {
+        return;
+    }
+
+    let Some(frame) = climb
+        .frames
+        .get(playback.frame)
+        .or_else(|| climb.frames.first())
+    else {
         return;
     };
 
-    for (placement_id, role_id) in placements {
+    for &FramePlacement {
+        placement_id,
+        role_id,
+    } in frame.iter()
+    {
+        if !active.shows_placement(&kilter, placement_id) {
+            continue;
+        }
+
         let indicator = commands
             .spawn(PlacementIndicator {
                 placement_id,
@@ -209,3 +718,62 @@ fn show_climb(
         commands.entity(board).add_child(indicator);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn app(uuids: &[&str], selected: usize) -> App {
+        let mut kilter = KilterData::default();
+        for uuid in uuids {
+            kilter.insert_climb(Climb {
+                uuid: uuid.to_string(),
+                ..default()
+            });
+        }
+
+        let mut app = App::new();
+        app.insert_resource(kilter)
+            .insert_resource(SelectedClimb(selected))
+            .add_systems(Update, keep_selected_climb);
+        app.update();
+        app
+    }
+
+    fn selected_uuid(app: &App) -> Option<String> {
+        let selected = app.world().resource::<SelectedClimb>();
+        app.world()
+            .resource::<KilterData>()
+            .climb_at(selected.0)
+            .map(|(uuid, _)| uuid.clone())
+    }
+
+    #[test]
+    fn keeps_the_selected_climb_when_earlier_climbs_are_removed() {
+        let mut app = app(&["a", "b", "c"], 2);
+
+        app.world_mut()
+            .resource_mut::<KilterData>()
+            .remove_climb("a");
+        app.update();
+
+        assert_eq!(selected_uuid(&app).as_deref(), Some("c"));
+    }
+
+    #[test]
+    fn selects_the_next_climb_when_the_selected_one_is_removed() {
+        let mut app = app(&["a", "b", "c"], 1);
+
+        app.world_mut()
+            .resource_mut::<KilterData>()
+            .remove_climb("b");
+        app.update();
+        assert_eq!(selected_uuid(&app).as_deref(), Some("c"));
+
+        app.world_mut()
+            .resource_mut::<KilterData>()
+            .remove_climb("c");
+        app.update();
+        assert_eq!(selected_uuid(&app).as_deref(), Some("a"));
+    }
+}
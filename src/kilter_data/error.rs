@@ -0,0 +1,165 @@
+use std::{collections::BTreeMap, fmt::Display};
+
+#[derive(Debug)]
+pub enum KilterDataError {
+    Io(std::io::Error),
+    #[cfg(not(any(target_arch = "wasm32", target_os = "android", target_os = "ios")))]
+    Sqlite(rusqlite::Error),
+    Json(serde_json::Error),
+    Binary(bincode::Error),
+    /// The data doesn't start with a snapshot's header.
+    NotASnapshot,
+    /// The snapshot was written by a different version of the app.
+    SnapshotVersion {
+        found: u16,
+        supported: u16,
+    },
+    /// The data source doesn't have the shape we expect, e.g. a missing table
+    /// or column, or a JSON update without a `PUT`, `PATCH` or `DELETE` object.
+    Schema {
+        table: &'static str,
+        message: String,
+    },
+    /// A single record couldn't be decoded. These are collected into a
+    /// [`LoadReport`] rather than aborting the whole load.
+    Record {
+        table: &'static str,
+        index: usize,
+        source: Box<KilterDataError>,
+    },
+}
+
+impl Display for KilterDataError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "io error: {e}"),
+            #[cfg(not(any(target_arch = "wasm32", target_os = "android", target_os = "ios")))]
+            Self::Sqlite(e) => write!(f, "sqlite error: {e}"),
+            Self::Json(e) => write!(f, "json error: {e}"),
+            Self::Binary(e) => write!(f, "binary error: {e}"),
+            Self::NotASnapshot => write!(f, "not a snapshot"),
+            Self::SnapshotVersion { found, supported } => write!(
+                f,
+                "snapshot version {found} is not supported, expected {supported}"
+            ),
+            Self::Schema { table, message } => write!(f, "schema mismatch in {table}: {message}"),
+            Self::Record {
+                table,
+                index,
+                source,
+            } => write!(f, "failed to decode {table} record {index}: {source}"),
+        }
+    }
+}
+
+impl std::error::Error for KilterDataError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(e) => Some(e),
+            #[cfg(not(any(target_arch = "wasm32", target_os = "android", target_os = "ios")))]
+            Self::Sqlite(e) => Some(e),
+            Self::Json(e) => Some(e),
+            Self::Binary(e) => Some(e),
+            Self::NotASnapshot | Self::SnapshotVersion { .. } => None,
+            Self::Schema { .. } => None,
+            Self::Record { source, .. } => Some(source.as_ref()),
+        }
+    }
+}
+
+impl From<std::io::Error> for KilterDataError {
+    fn from(e: std::io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+#[cfg(not(any(target_arch = "wasm32", target_os = "android", target_os = "ios")))]
+impl From<rusqlite::Error> for KilterDataError {
+    fn from(e: rusqlite::Error) -> Self {
+        Self::Sqlite(e)
+    }
+}
+
+impl From<bincode::Error> for KilterDataError {
+    fn from(e: bincode::Error) -> Self {
+        Self::Binary(e)
+    }
+}
+
+impl From<serde_json::Error> for KilterDataError {
+    fn from(e: serde_json::Error) -> Self {
+        Self::Json(e)
+    }
+}
+
+/// Non-fatal problems encountered while loading data, and what JSON updates
+/// changed.
+#[derive(Debug, Default)]
+pub struct LoadReport {
+    pub errors: Vec<KilterDataError>,
+    /// Changes by table name.
+    pub changes: BTreeMap<&'static str, TableChanges>,
+}
+
+impl LoadReport {
+    pub fn is_ok(&self) -> bool {
+        self.errors.is_empty()
+    }
+
+    pub fn record_error(
+        &mut self,
+        table: &'static str,
+        index: usize,
+        source: impl Into<KilterDataError>,
+    ) {
+        self.errors.push(KilterDataError::Record {
+            table,
+            index,
+            source: Box::new(source.into()),
+        });
+    }
+
+    pub(super) fn record_change(&mut self, table: &'static str, change: Change) {
+        let changes = self.changes.entry(table).or_default();
+        match change {
+            Change::Added => changes.added += 1,
+            Change::Updated => changes.updated += 1,
+            Change::Removed => changes.removed += 1,
+        }
+    }
+
+    pub fn extend(&mut self, other: LoadReport) {
+        self.errors.extend(other.errors);
+        for (table, other) in other.changes {
+            let changes = self.changes.entry(table).or_default();
+            changes.added += other.added;
+            changes.updated += other.updated;
+            changes.removed += other.removed;
+        }
+    }
+}
+
+/// How many records of a table an update added, updated and removed.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct TableChanges {
+    pub added: usize,
+    pub updated: usize,
+    pub removed: usize,
+}
+
+impl Display for TableChanges {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} added, {} updated, {} removed",
+            self.added, self.updated, self.removed
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub(super) enum Change {
+    Added,
+    Updated,
+    Removed,
+}
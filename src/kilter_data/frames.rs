@@ -0,0 +1,495 @@
+use std::{
+    fmt::{self, Display},
+    str::FromStr,
+};
+
+use combine::{
+    eof, from_str, many1,
+    parser::char::{char, digit, spaces},
+    satisfy, sep_by1, skip_many,
+    stream::{
+        easy,
+        position::{self, IndexPositioner},
+        RangeStream,
+    },
+    EasyParser, ParseError, Parser,
+};
+use serde_derive::{Deserialize, Serialize};
+
+/// A placement lit with a particular role, written as e.g. `p1145r12`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FramePlacement {
+    pub placement_id: u32,
+    pub role_id: u32,
+}
+impl Display for FramePlacement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "p{}r{}", self.placement_id, self.role_id)
+    }
+}
+
+/// The placements lit at the same time.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Frame(pub Vec<FramePlacement>);
+impl Frame {
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &FramePlacement> {
+        self.0.iter()
+    }
+
+    pub fn role_of(&self, placement_id: u32) -> Option<u32> {
+        self.0
+            .iter()
+            .find(|p| p.placement_id == placement_id)
+            .map(|p| p.role_id)
+    }
+
+    /// Gives `placement_id` the role `role_id`, adding it if it's missing, or
+    /// removes it if `role_id` is `None`.
+    pub fn set_role(&mut self, placement_id: u32, role_id: Option<u32>) {
+        let existing = self.0.iter().position(|p| p.placement_id == placement_id);
+        match (existing, role_id) {
+            (Some(i), Some(role_id)) => self.0[i].role_id = role_id,
+            (Some(i), None) => {
+                self.0.remove(i);
+            }
+            (None, Some(role_id)) => self.0.push(FramePlacement {
+                placement_id,
+                role_id,
+            }),
+            (None, None) => {}
+        }
+    }
+
+    /// Placement ids that appear more than once, in order of their second
+    /// appearance.
+    pub fn duplicate_placements(&self) -> Vec<u32> {
+        let mut duplicates = vec![];
+        for (i, placement) in self.0.iter().enumerate() {
+            if self.0[..i]
+                .iter()
+                .any(|p| p.placement_id == placement.placement_id)
+                && !duplicates.contains(&placement.placement_id)
+            {
+                duplicates.push(placement.placement_id);
+            }
+        }
+        duplicates
+    }
+}
+impl FromIterator<FramePlacement> for Frame {
+    fn from_iter<T: IntoIterator<Item = FramePlacement>>(iter: T) -> Self {
+        Self(iter.into_iter().collect())
+    }
+}
+impl Display for Frame {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for placement in &self.0 {
+            write!(f, "{placement}")?;
+        }
+        Ok(())
+    }
+}
+
+/// The ordered frames of a climb, written as e.g. `p1r12p2r13,p3r14`.
+///
+/// Most climbs have a single frame. Empty frames can't be represented in the
+/// text format, so they don't survive a round trip.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Frames(pub Vec<Frame>);
+impl Frames {
+    /// Parses frames, tolerating whitespace, `;` or `|` between placements,
+    /// lowercase or uppercase `p`/`r` and surrounding quotes.
+    pub fn parse(input: &str) -> Result<Self, FramesError> {
+        let trimmed = input.trim();
+        let unquoted = trimmed
+            .strip_prefix('"')
+            .and_then(|s| s.strip_suffix('"'))
+            .or_else(|| {
+                trimmed
+                    .strip_prefix('\'')
+                    .and_then(|s| s.strip_suffix('\''))
+            })
+            .unwrap_or(trimmed);
+
+        if unquoted.chars().all(is_separator) {
+            return Ok(Self::default());
+        }
+
+        match (separators(), frames(), eof())
+            .map(|(_, frames, _)| frames)
+            .easy_parse(position::Stream::with_positioner(
+                unquoted,
+                IndexPositioner::new(),
+            )) {
+            Ok((frames, _remaining_input)) => Ok(frames),
+            Err(err) => {
+                let offset = input.len() - input.trim_start().len()
+                    + usize::from(unquoted.len() != trimmed.len());
+                Err(FramesError::new(input, unquoted, offset, err))
+            }
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Frame> {
+        self.0.iter()
+    }
+
+    pub fn first(&self) -> Option<&Frame> {
+        self.0.first()
+    }
+
+    pub fn get(&self, index: usize) -> Option<&Frame> {
+        self.0.get(index)
+    }
+
+    /// Replaces the frame at `index`, keeping any others, or appends it if
+    /// `index` is past the end.
+    pub fn set(&mut self, index: usize, frame: Frame) {
+        match self.0.get_mut(index) {
+            Some(existing) => *existing = frame,
+            None => self.0.push(frame),
+        }
+    }
+
+    /// Whether `placement_id` is in any frame, with `role_id` if given.
+    pub fn contains(&self, placement_id: u32, role_id: Option<u32>) -> bool {
+        self.0
+            .iter()
+            .flat_map(Frame::iter)
+            .any(|p| p.placement_id == placement_id && role_id.is_none_or(|r| p.role_id == r))
+    }
+
+    /// Every placement id used in any frame, sorted and without duplicates.
+    pub fn placement_ids(&self) -> Vec<u32> {
+        let mut ids: Vec<_> = self
+            .0
+            .iter()
+            .flat_map(Frame::iter)
+            .map(|p| p.placement_id)
+            .collect();
+        ids.sort_unstable();
+        ids.dedup();
+        ids
+    }
+
+    /// Placement ids that appear more than once within a single frame.
+    pub fn duplicate_placements(&self) -> Vec<u32> {
+        let mut duplicates = vec![];
+        for frame in &self.0 {
+            for id in frame.duplicate_placements() {
+                if !duplicates.contains(&id) {
+                    duplicates.push(id);
+                }
+            }
+        }
+        duplicates
+    }
+}
+impl From<Frame> for Frames {
+    fn from(frame: Frame) -> Self {
+        Self(vec![frame])
+    }
+}
+impl Display for Frames {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, frame) in self.0.iter().enumerate() {
+            if i > 0 {
+                write!(f, ",")?;
+            }
+            write!(f, "{frame}")?;
+        }
+        Ok(())
+    }
+}
+impl FromStr for Frames {
+    type Err = FramesError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+impl TryFrom<String> for Frames {
+    type Error = FramesError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        Self::parse(&value)
+    }
+}
+impl From<Frames> for String {
+    fn from(frames: Frames) -> Self {
+        frames.to_string()
+    }
+}
+
+/// Describes where and why frames couldn't be parsed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FramesError {
+    /// Byte offset into the original input where parsing failed.
+    pub offset: usize,
+    /// What would have been accepted at `offset`, e.g. `` `p` ``.
+    pub expected: Vec<String>,
+    /// Additional detail, e.g. for a number that doesn't fit.
+    pub message: Option<String>,
+    /// The input starting at `offset`, or empty at the end of the input.
+    pub snippet: String,
+}
+impl FramesError {
+    pub(crate) const SNIPPET_LEN: usize = 12;
+
+    fn new(
+        input: &str,
+        parsed: &str,
+        parsed_offset: usize,
+        err: easy::Errors<char, &str, usize>,
+    ) -> Self {
+        let offset = parsed_offset
+            + parsed
+                .char_indices()
+                .nth(err.position)
+                .map(|(i, _)| i)
+                .unwrap_or(parsed.len());
+
+        let mut expected = vec![];
+        let mut message = None;
+        for error in err.errors {
+            match error {
+                easy::Error::Expected(info) => {
+                    let info = info.to_string();
+                    if !expected.contains(&info) {
+                        expected.push(info);
+                    }
+                }
+                easy::Error::Message(info) => message = Some(info.to_string()),
+                easy::Error::Other(e) => message = Some(e.to_string()),
+                easy::Error::Unexpected(_) => {}
+            }
+        }
+
+        Self {
+            offset,
+            expected,
+            message,
+            snippet: input[offset..].chars().take(Self::SNIPPET_LEN).collect(),
+        }
+    }
+}
+impl Display for FramesError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "at byte {}", self.offset)?;
+
+        if self.snippet.is_empty() {
+            write!(f, " (end of input)")?;
+        } else {
+            write!(f, " (`{}`)", self.snippet)?;
+        }
+
+        if let Some(message) = &self.message {
+            write!(f, ": {message}")?;
+        }
+
+        match self.expected.as_slice() {
+            [] => Ok(()),
+            [one] => write!(f, ": expected {one}"),
+            [rest @ .., last] => write!(f, ": expected {} or {last}", rest.join(", ")),
+        }
+    }
+}
+impl std::error::Error for FramesError {}
+
+fn is_separator(c: char) -> bool {
+    c.is_whitespace() || c == ';' || c == '|'
+}
+
+fn separators<'a, I>() -> impl Parser<I, Output = ()>
+where
+    I: RangeStream<Token = char, Range = &'a str>,
+    I::Error: ParseError<I::Token, I::Range, I::Position>,
+{
+    skip_many(satisfy(is_separator))
+}
+
+fn letter<'a, I>(lower: char) -> impl Parser<I, Output = char>
+where
+    I: RangeStream<Token = char, Range = &'a str>,
+    I::Error: ParseError<I::Token, I::Range, I::Position>,
+{
+    satisfy(move |c: char| c.to_ascii_lowercase() == lower).expected(lower)
+}
+
+fn number<'a, I>() -> impl Parser<I, Output = u32>
+where
+    I: RangeStream<Token = char, Range = &'a str>,
+    I::Error: ParseError<I::Token, I::Range, I::Position>,
+{
+    from_str(many1::<String, _, _>(digit()))
+}
+
+pub fn placement_and_role<'a, I>() -> impl Parser<I, Output = FramePlacement>
+where
+    I: RangeStream<Token = char, Range = &'a str>,
+    I::Error: ParseError<I::Token, I::Range, I::Position>,
+{
+    (
+        letter('p'),
+        spaces(),
+        number(),
+        spaces(),
+        letter('r'),
+        spaces(),
+        number(),
+    )
+        .map(|(_, _, placement_id, _, _, _, role_id)| FramePlacement {
+            placement_id,
+            role_id,
+        })
+}
+
+/// Frames separated by `,`, each made of placements optionally separated by
+/// whitespace, `;` or `|`.
+pub fn frames<'a, I>() -> impl Parser<I, Output = Frames>
+where
+    I: RangeStream<Token = char, Range = &'a str>,
+    I::Error: ParseError<I::Token, I::Range, I::Position>,
+{
+    let frame = many1((placement_and_role(), separators()).map(|(p, _)| p)).map(Frame);
+
+    sep_by1(frame, (char(','), separators())).map(Frames)
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+
+    use super::*;
+
+    fn placement() -> impl Strategy<Value = FramePlacement> {
+        (any::<u32>(), any::<u32>()).prop_map(|(placement_id, role_id)| FramePlacement {
+            placement_id,
+            role_id,
+        })
+    }
+
+    fn frame() -> impl Strategy<Value = Frame> {
+        prop::collection::vec(placement(), 1..20).prop_map(Frame)
+    }
+
+    fn frames() -> impl Strategy<Value = Frames> {
+        prop::collection::vec(frame(), 1..5).prop_map(Frames)
+    }
+
+    /// Writes `frames` the way people paste them, with arbitrary separators,
+    /// case and quoting.
+    fn messy(frames: &Frames, separators: &[&str], upper: &[bool], quote: &str) -> String {
+        let mut out = quote.to_string();
+        let mut i = 0;
+        for (f, frame) in frames.iter().enumerate() {
+            if f > 0 {
+                out.push(',');
+                out.push_str(separators[i % separators.len()]);
+            }
+            for p in frame.iter() {
+                let (p_letter, r_letter) = if upper[i % upper.len()] {
+                    ("P", "R")
+                } else {
+                    ("p", "r")
+                };
+                out.push_str(&format!(
+                    "{p_letter}{}{r_letter}{}",
+                    p.placement_id, p.role_id
+                ));
+                out.push_str(separators[i % separators.len()]);
+                i += 1;
+            }
+        }
+        out.push_str(quote);
+        out
+    }
+
+    proptest! {
+        #[test]
+        fn frames_round_trip(frames in frames()) {
+            prop_assert_eq!(Frames::parse(&frames.to_string()), Ok(frames));
+        }
+
+        #[test]
+        fn frame_round_trips(frame in frame()) {
+            prop_assert_eq!(Frames::parse(&frame.to_string()), Ok(Frames::from(frame)));
+        }
+
+        #[test]
+        fn lenient_input_parses_the_same(
+            frames in frames(),
+            separators in prop::collection::vec(
+                prop::sample::select(vec!["", " ", ";", "|", "\t", " ; "]),
+                1..5,
+            ),
+            upper in prop::collection::vec(any::<bool>(), 1..5),
+            quote in prop::sample::select(vec!["", "\"", "'"]),
+            padding in prop::sample::select(vec!["", " ", "\n  "]),
+        ) {
+            let input = format!("{padding}{}{padding}", messy(&frames, &separators, &upper, quote));
+            prop_assert_eq!(Frames::parse(&input), Ok(frames));
+        }
+
+        #[test]
+        fn errors_point_into_the_input(input in "\\PC{0,40}") {
+            if let Err(err) = Frames::parse(&input) {
+                prop_assert!(input.is_char_boundary(err.offset));
+                prop_assert!(input[err.offset..].starts_with(&err.snippet));
+            }
+        }
+    }
+
+    #[test]
+    fn blank_input_has_no_frames() {
+        assert_eq!(Frames::parse(""), Ok(Frames::default()));
+        assert_eq!(Frames::parse(" ; | "), Ok(Frames::default()));
+    }
+
+    #[test]
+    fn error_reports_offset_and_snippet() {
+        let err = Frames::parse("p1r12p2x13").unwrap_err();
+        assert_eq!(err.offset, 7);
+        assert_eq!(err.snippet, "x13");
+        assert!(err.expected.contains(&"`r`".to_string()), "{err:?}");
+    }
+
+    #[test]
+    fn error_offset_accounts_for_whitespace_and_quotes() {
+        let err = Frames::parse("  \"p1r2,q3r4\"").unwrap_err();
+        assert_eq!(err.offset, 8);
+        assert_eq!(err.snippet, "q3r4\"");
+    }
+
+    #[test]
+    fn error_at_end_of_input_has_empty_snippet() {
+        let err = Frames::parse("p1r").unwrap_err();
+        assert_eq!(err.offset, 3);
+        assert_eq!(err.snippet, "");
+        assert!(err.to_string().contains("end of input"));
+    }
+
+    #[test]
+    fn number_too_large_is_reported() {
+        let err = Frames::parse("p99999999999r1").unwrap_err();
+        assert_eq!(err.offset, 1);
+        assert!(err.message.is_some(), "{err:?}");
+    }
+}
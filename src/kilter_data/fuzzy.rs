@@ -0,0 +1,291 @@
+use super::Climb;
+
+/// Ranks climbs by how well their name, setter and description match a search.
+///
+/// Matching ignores case and diacritics, prefers matches at the start of a
+/// word and tolerates small typos in longer words.
+pub struct FuzzyQuery {
+    query: String,
+    tokens: Vec<String>,
+}
+
+/// The normalized name, setter and description of a climb, computed once when
+/// the climb is added rather than on every search.
+#[derive(Debug, Clone, Default)]
+pub struct ClimbText {
+    name: String,
+    setter: String,
+    description: String,
+    /// [`char_mask`] of all three fields.
+    chars: u64,
+}
+impl ClimbText {
+    pub fn new(climb: &Climb) -> Self {
+        let name = normalize(&climb.name);
+        let setter = normalize(&climb.setter_username);
+        let description = normalize(&climb.description);
+        let chars = char_mask(&name) | char_mask(&setter) | char_mask(&description);
+
+        Self {
+            name,
+            setter,
+            description,
+            chars,
+        }
+    }
+}
+
+const NAME_WEIGHT: u32 = 3;
+const SETTER_WEIGHT: u32 = 2;
+const DESCRIPTION_WEIGHT: u32 = 1;
+
+impl FuzzyQuery {
+    pub fn new(query: &str) -> Self {
+        let query = normalize(query);
+        let tokens = words(&query).map(str::to_string).collect();
+        Self { query, tokens }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tokens.is_empty()
+    }
+
+    /// Scores the climb at `idx`, or returns `None` if some word of the query
+    /// doesn't match anywhere.
+    pub fn score(&self, idx: usize, text: &ClimbText) -> Option<u32> {
+        if self.is_empty() {
+            return Some(0);
+        }
+
+        // Searching by index is still supported.
+        if idx.to_string() == self.query {
+            return Some(u32::MAX);
+        }
+
+        // Every match needs most of a word's characters somewhere in the
+        // climb, which rules out most climbs before comparing any words.
+        if !self
+            .tokens
+            .iter()
+            .all(|token| could_match(token, text.chars))
+        {
+            return None;
+        }
+
+        let fields = [
+            (&text.name, NAME_WEIGHT),
+            (&text.setter, SETTER_WEIGHT),
+            (&text.description, DESCRIPTION_WEIGHT),
+        ];
+
+        let mut score = 0;
+
+        for token in &self.tokens {
+            let best = fields
+                .iter()
+                .map(|(field, weight)| token_score(token, field) * weight)
+                .max()
+                .unwrap_or(0);
+
+            if best == 0 {
+                return None;
+            }
+
+            score += best;
+        }
+
+        // Reward matching the whole query, especially at the start of the name.
+        for (field, weight) in &fields {
+            if **field == self.query {
+                score += 100 * weight;
+            } else if field.starts_with(&self.query) {
+                score += 50 * weight;
+            } else if field.contains(&self.query) {
+                score += 20 * weight;
+            }
+        }
+
+        Some(score)
+    }
+}
+
+fn token_score(token: &str, field: &str) -> u32 {
+    let mut best = 0;
+
+    for word in words(field) {
+        let score = if word == token {
+            30
+        } else if word.starts_with(token) {
+            20
+        } else if word.contains(token) {
+            10
+        } else if is_typo(token, word) {
+            5
+        } else {
+            0
+        };
+        best = best.max(score);
+    }
+
+    best
+}
+
+/// How many typos to tolerate in a word of `len` characters.
+fn max_edits(len: usize) -> usize {
+    match len {
+        0..=3 => 0,
+        4..=7 => 1,
+        _ => 2,
+    }
+}
+
+/// A set of the characters in `s`, folded into 64 bits.
+fn char_mask(s: &str) -> u64 {
+    s.chars().fold(0, |mask, c| mask | char_bit(c))
+}
+
+fn char_bit(c: char) -> u64 {
+    1 << (c as u32 % 64)
+}
+
+/// Whether `token` could match text containing the characters in `chars`.
+///
+/// A match, even with typos, keeps all but [`max_edits`] of the token's
+/// characters, so this never rules out a real match.
+fn could_match(token: &str, chars: u64) -> bool {
+    let len = token.chars().count();
+    let found = token.chars().filter(|&c| chars & char_bit(c) != 0).count();
+
+    found + max_edits(len) >= len
+}
+
+/// Whether `token` is within a few edits of `word` or of a prefix of `word`
+/// with the same length.
+fn is_typo(token: &str, word: &str) -> bool {
+    let max_edits = max_edits(token.chars().count());
+    if max_edits == 0 || !could_match(token, char_mask(word)) {
+        return false;
+    }
+
+    let token: Vec<char> = token.chars().collect();
+    let word: Vec<char> = word.chars().collect();
+
+    if word.len() + max_edits < token.len() {
+        return false;
+    }
+
+    let prefix = &word[..word.len().min(token.len() + max_edits)];
+
+    // The distance to the closest prefix of `word`.
+    edit_distances(&token, prefix)
+        .into_iter()
+        .skip(token.len().saturating_sub(max_edits))
+        .min()
+        .is_some_and(|d| d <= max_edits)
+}
+
+/// Levenshtein distances between `a` and each prefix of `b`.
+fn edit_distances(a: &[char], b: &[char]) -> Vec<usize> {
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    let mut row = vec![0; b.len() + 1];
+
+    for (i, ca) in a.iter().enumerate() {
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let cost = usize::from(ca != cb);
+            row[j + 1] = (prev[j] + cost).min(prev[j + 1] + 1).min(row[j] + 1);
+        }
+        std::mem::swap(&mut prev, &mut row);
+    }
+
+    prev
+}
+
+fn words(s: &str) -> impl Iterator<Item = &str> {
+    s.split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+}
+
+/// Lowercases and strips diacritics from common Latin characters.
+pub fn normalize(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars().flat_map(char::to_lowercase) {
+        match fold_diacritic(c) {
+            Some(folded) => out.push_str(folded),
+            None => out.push(c),
+        }
+    }
+    out
+}
+
+fn fold_diacritic(c: char) -> Option<&'static str> {
+    Some(match c {
+        'à' | 'á' | 'â' | 'ã' | 'ä' | 'å' | 'ā' | 'ă' | 'ą' => "a",
+        'æ' => "ae",
+        'ç' | 'ć' | 'ĉ' | 'ċ' | 'č' => "c",
+        'ď' | 'đ' => "d",
+        'è' | 'é' | 'ê' | 'ë' | 'ē' | 'ĕ' | 'ė' | 'ę' | 'ě' => "e",
+        'ĝ' | 'ğ' | 'ġ' | 'ģ' => "g",
+        'ĥ' | 'ħ' => "h",
+        'ì' | 'í' | 'î' | 'ï' | 'ĩ' | 'ī' | 'ĭ' | 'į' | 'ı' => "i",
+        'ĵ' => "j",
+        'ķ' => "k",
+        'ĺ' | 'ļ' | 'ľ' | 'ŀ' | 'ł' => "l",
+        'ñ' | 'ń' | 'ņ' | 'ň' => "n",
+        'ò' | 'ó' | 'ô' | 'õ' | 'ö' | 'ø' | 'ō' | 'ŏ' | 'ő' => "o",
+        'œ' => "oe",
+        'ŕ' | 'ŗ' | 'ř' => "r",
+        'ś' | 'ŝ' | 'ş' | 'š' => "s",
+        'ß' => "ss",
+        'ţ' | 'ť' | 'ŧ' => "t",
+        'ù' | 'ú' | 'û' | 'ü' | 'ũ' | 'ū' | 'ŭ' | 'ů' | 'ű' | 'ų' => "u",
+        'ŵ' => "w",
+        'ý' | 'ÿ' | 'ŷ' => "y",
+        'ź' | 'ż' | 'ž' => "z",
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(name: &str, setter: &str) -> ClimbText {
+        ClimbText::new(&Climb {
+            name: name.to_string(),
+            setter_username: setter.to_string(),
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn matches_words_ignoring_case_and_diacritics() {
+        let climb = text("Crêpe Suzette", "Jörg");
+
+        assert!(FuzzyQuery::new("crepe").score(0, &climb).is_some());
+        assert!(FuzzyQuery::new("SUZ jorg").score(0, &climb).is_some());
+        assert!(FuzzyQuery::new("crepe waffle").score(0, &climb).is_none());
+    }
+
+    #[test]
+    fn tolerates_typos_in_longer_words() {
+        let climb = text("Overhang Party", "setter");
+
+        assert!(FuzzyQuery::new("ovrehang").score(0, &climb).is_some());
+        assert!(FuzzyQuery::new("overhnag").score(0, &climb).is_some());
+        assert!(FuzzyQuery::new("prty").score(0, &climb).is_some());
+        assert!(FuzzyQuery::new("pxrty").score(0, &climb).is_some());
+        assert!(FuzzyQuery::new("pzrqy").score(0, &climb).is_none());
+        // Short words must match exactly.
+        assert!(FuzzyQuery::new("ovr").score(0, &climb).is_none());
+    }
+
+    #[test]
+    fn ranks_name_matches_first() {
+        let by_name = text("Moonlight", "someone");
+        let by_setter = text("Something", "moonlight");
+        let query = FuzzyQuery::new("moonlight");
+
+        assert!(query.score(0, &by_name) > query.score(0, &by_setter));
+    }
+}
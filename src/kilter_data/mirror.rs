@@ -0,0 +1,93 @@
+use std::collections::HashMap;
+
+use uuid::Uuid;
+
+use super::{Climb, ClimbOrigin, Frame, FramePlacement, Frames, KilterData};
+
+/// A climb flipped left to right by [`KilterData::mirror_climb`].
+pub struct MirroredClimb {
+    pub climb: Climb,
+    /// Placements with no mirrored counterpart on the layout. They're left
+    /// out of the mirrored climb.
+    pub unmirrored: Vec<u32>,
+}
+
+impl KilterData {
+    /// The placement on the same layout whose hole mirrors `placement_id`'s.
+    pub fn mirror_placement(&self, placement_id: u32) -> Option<u32> {
+        let placement = self.placements.get(&placement_id)?;
+        let hole = self.holes.get(&placement.hole_id)?;
+
+        self.placements
+            .values()
+            .find(|p| p.layout_id == placement.layout_id && p.hole_id == hole.mirrored_hole_id)
+            .map(|p| p.id)
+    }
+
+    /// Creates a new climb with every placement swapped for its mirror image.
+    pub fn mirror_climb(&self, climb: &Climb) -> MirroredClimb {
+        let mut mirrored_ids = HashMap::new();
+        let mut unmirrored = vec![];
+
+        for placement_id in climb.frames.placement_ids() {
+            match self.mirror_placement(placement_id) {
+                Some(mirrored) => {
+                    mirrored_ids.insert(placement_id, mirrored);
+                }
+                None => unmirrored.push(placement_id),
+            }
+        }
+
+        let frames = Frames(
+            climb
+                .frames
+                .iter()
+                .map(|frame| {
+                    frame
+                        .iter()
+                        .filter_map(|p| {
+                            mirrored_ids.get(&p.placement_id).map(|id| FramePlacement {
+                                placement_id: *id,
+                                role_id: p.role_id,
+                            })
+                        })
+                        .collect::<Frame>()
+                })
+                .collect(),
+        );
+
+        let mut mirrored = Climb {
+            uuid: Uuid::new_v4().simple().to_string(),
+            name: format!("{} (Mirrored)", climb.name),
+            frames_count: frames.len() as u32,
+            frames,
+            created_at: String::new(),
+            origin: ClimbOrigin::Authored,
+            ..climb.clone()
+        };
+
+        // Holes mirror around the same vertical line, so any pair gives the
+        // line to flip the edges around.
+        let center_x2 = mirrored_ids.iter().find_map(|(from, to)| {
+            let x = |id| {
+                self.placements
+                    .get(id)
+                    .and_then(|p| self.holes.get(&p.hole_id))
+                    .map(|h| h.x)
+            };
+            Some(x(from)? + x(to)?)
+        });
+
+        if let Some(center_x2) = center_x2 {
+            if climb.edge_left != 0 || climb.edge_right != 0 {
+                mirrored.edge_left = center_x2 - climb.edge_right;
+                mirrored.edge_right = center_x2 - climb.edge_left;
+            }
+        }
+
+        MirroredClimb {
+            climb: mirrored,
+            unmirrored,
+        }
+    }
+}
@@ -0,0 +1,1084 @@
+use bevy::platform::collections::{HashMap, HashSet};
+use indexmap::IndexMap;
+use serde_derive::{Deserialize, Serialize};
+use std::{
+    collections::BTreeSet,
+    sync::atomic::{AtomicU64, Ordering},
+    time::Duration,
+};
+#[cfg(not(target_arch = "wasm32"))]
+use std::{fs::read_dir, fs::File, io::BufReader, path::Path};
+
+#[cfg(not(any(target_arch = "wasm32", target_os = "android", target_os = "ios")))]
+use rusqlite::{types::Type, Connection, OpenFlags, Row};
+
+use bevy::prelude::*;
+
+use fuzzy::ClimbText;
+
+pub use error::{KilterDataError, LoadReport, TableChanges};
+pub use frames::{Frame, FramePlacement, Frames, FramesError};
+pub use mirror::MirroredClimb;
+pub use search::{ClimbQuery, ClimbSort, HoldFilter, HoldMatch};
+pub use snapshot::{check_snapshot, SNAPSHOT_HEADER_LEN, SNAPSHOT_VERSION};
+pub use validation::ClimbIssue;
+
+mod error;
+mod frames;
+mod fuzzy;
+mod mirror;
+mod search;
+mod sets;
+mod snapshot;
+mod update;
+mod validation;
+
+/// The Kilter Board app database loaded on native.
+#[cfg(not(target_arch = "wasm32"))]
+pub const DATABASE_PATH: &str = "../kilter_brain_data/db.sqlite3";
+/// JSON updates loaded after the database, in order of their file names.
+#[cfg(not(target_arch = "wasm32"))]
+pub const UPDATES_PATH: &str = "../kilter_brain_data/api_json";
+/// A snapshot of the database and JSON updates, which loads much faster.
+#[cfg(not(target_arch = "wasm32"))]
+pub const SNAPSHOT_PATH: &str = "../kilter_brain_data/snapshot.kilter";
+
+#[derive(Resource, Default)]
+pub struct KilterData {
+    pub holes: HashMap<u32, Hole>,
+    pub placements: HashMap<u32, Placement>,
+    pub placement_roles: HashMap<u32, PlacementRole>,
+    /// Only changed through [`KilterData::insert_climb`] and friends so that
+    /// searching by placement stays accurate.
+    climbs: IndexMap<String, Climb>,
+    pub products: HashMap<u32, Product>,
+    /// The angles each product can be set to, by product id.
+    pub products_angles: HashMap<u32, BTreeSet<u32>>,
+    pub product_sizes: HashMap<u32, ProductSize>,
+    pub layouts: HashMap<u32, Layout>,
+    /// Groups of holds that can be installed separately, e.g. bolt ons.
+    pub sets: HashMap<u32, Set>,
+    /// The images of each set's holds on each layout and product size.
+    pub product_sizes_layouts_sets: HashMap<u32, ProductSizeLayoutSet>,
+    /// The position in the board's LED strip of each hole, by product size.
+    pub leds: HashMap<u32, Led>,
+    /// Stats by climb uuid and angle.
+    pub climb_stats: HashMap<String, HashMap<u32, ClimbStats>>,
+    pub difficulty_grades: HashMap<u32, DifficultyGrade>,
+    /// Sorted indices into `climbs` by placement id.
+    placement_climbs: HashMap<u32, Vec<usize>>,
+    /// The normalized text of each climb by uuid, for searching by name.
+    climb_text: HashMap<String, ClimbText>,
+    /// The sets each climb uses by uuid, sorted by id.
+    climb_sets: HashMap<String, Vec<u32>>,
+    /// The sets placements on each layout belong to, sorted by id.
+    layout_sets: HashMap<u32, Vec<u32>>,
+    /// Listed set images by product size, layout and set id.
+    set_images: HashMap<(u32, u32, u32), String>,
+    /// LED positions by product size and hole id.
+    led_positions: HashMap<(u32, u32), u32>,
+    /// See [`KilterData::placements_generation`].
+    placements_generation: u64,
+    /// The generation each climb was last inserted or removed at since
+    /// loading, by uuid.
+    climb_generations: HashMap<String, u64>,
+    /// The latest of `climb_generations`.
+    climbs_generation: u64,
+}
+
+/// Hands out generations for placements and climbs, so that they're never
+/// reused even when [`KilterData`] is replaced.
+static NEXT_GENERATION: AtomicU64 = AtomicU64::new(1);
+
+impl KilterData {
+    /// Loads data from a Kilter Board app database.
+    ///
+    /// Rows that fail to decode are skipped and reported in the returned
+    /// [`LoadReport`].
+    #[cfg(not(any(target_arch = "wasm32", target_os = "android", target_os = "ios")))]
+    pub fn from_sqlite(path: &str) -> Result<(Self, LoadReport), KilterDataError> {
+        Self::from_connection(&open_database(path)?)
+    }
+
+    /// Loads data from the bytes of a Kilter Board app database, e.g. as read
+    /// by the asset server.
+    #[cfg(not(any(target_arch = "wasm32", target_os = "android", target_os = "ios")))]
+    pub fn from_sqlite_bytes(bytes: &[u8]) -> Result<(Self, LoadReport), KilterDataError> {
+        Self::from_connection(&deserialize_database(bytes)?)
+    }
+
+    #[cfg(not(any(target_arch = "wasm32", target_os = "android", target_os = "ios")))]
+    fn from_connection(conn: &Connection) -> Result<(Self, LoadReport), KilterDataError> {
+        let mut report = LoadReport::default();
+
+        let holes = query_table(
+            conn,
+            "holes",
+            "SELECT
+                id, product_id, name, x, y, mirrored_hole_id, mirror_group
+            FROM holes",
+            &mut report,
+            |row| {
+                Ok((
+                    row.get(0)?,
+                    Hole {
+                        id: row.get(0)?,
+                        product_id: row.get(1)?,
+                        name: row.get(2)?,
+                        x: row.get(3)?,
+                        y: row.get(4)?,
+                        mirrored_hole_id: row.get(5)?,
+                        mirror_group: row.get(6)?,
+                    },
+                ))
+            },
+        )?;
+
+        let placements = query_table(
+            conn,
+            "placements",
+            "SELECT
+                id, layout_id, hole_id, set_id, default_placement_role_id
+            FROM placements",
+            &mut report,
+            |row| {
+                Ok((
+                    row.get(0)?,
+                    Placement {
+                        id: row.get(0)?,
+                        layout_id: row.get(1)?,
+                        hole_id: row.get(2)?,
+                        set_id: row.get(3)?,
+                        default_placement_role_id: row.get(4)?,
+                    },
+                ))
+            },
+        )?;
+
+        // Not every version of the database has role counts.
+        let count_columns = if has_column(conn, "placement_roles", "min_count_in_climb") {
+            "min_count_in_climb, max_count_in_climb"
+        } else {
+            "NULL, NULL"
+        };
+
+        let placement_roles = query_table(
+            conn,
+            "placement_roles",
+            &format!(
+                "SELECT
+                    id, product_id, position, name, full_name, led_color, screen_color,
+                    {count_columns}
+                FROM placement_roles"
+            ),
+            &mut report,
+            |row| {
+                Ok((
+                    row.get(0)?,
+                    PlacementRole {
+                        id: row.get(0)?,
+                        product_id: row.get(1)?,
+                        position: row.get(2)?,
+                        name: row.get(3)?,
+                        full_name: row.get(4)?,
+                        led_color: row.get(5)?,
+                        screen_color: row.get(6)?,
+                        min_count_in_climb: row.get(7)?,
+                        max_count_in_climb: row.get(8)?,
+                    },
+                ))
+            },
+        )?;
+
+        let climbs = query_table(
+            conn,
+            "climbs",
+            "SELECT
+                uuid, name, description, hsm,
+                edge_left, edge_right, edge_bottom, edge_top,
+                frames_count, frames_pace, frames, setter_id, setter_username,
+                layout_id, is_draft, is_listed, angle, created_at
+            FROM climbs",
+            &mut report,
+            |row| {
+                Ok((
+                    row.get(0)?,
+                    Climb {
+                        uuid: row.get(0)?,
+                        name: row.get(1)?,
+                        description: row.get(2)?,
+                        hsm: row.get(3)?,
+                        edge_left: row.get(4)?,
+                        edge_right: row.get(5)?,
+                        edge_bottom: row.get(6)?,
+                        edge_top: row.get(7)?,
+                        frames_count: row.get(8)?,
+                        frames_pace: row.get(9)?,
+                        frames: row.get::<_, String>(10)?.parse().map_err(|e| {
+                            rusqlite::Error::FromSqlConversionFailure(10, Type::Text, Box::new(e))
+                        })?,
+                        setter_id: row.get(11)?,
+                        setter_username: row.get(12)?,
+                        layout_id: row.get(13)?,
+                        is_draft: row.get(14)?,
+                        is_listed: row.get(15)?,
+                        angle: row.get(16)?,
+                        created_at: row.get(17)?,
+                        origin: ClimbOrigin::Database,
+                    },
+                ))
+            },
+        )?;
+
+        let products = query_table(
+            conn,
+            "products",
+            "SELECT
+                id, name, is_listed
+            FROM products",
+            &mut report,
+            |row| {
+                Ok((
+                    row.get(0)?,
+                    Product {
+                        id: row.get(0)?,
+                        name: row.get(1)?,
+                        is_listed: row.get(2)?,
+                    },
+                ))
+            },
+        )?;
+
+        let mut products_angles: HashMap<u32, BTreeSet<u32>> = HashMap::default();
+        // Older databases don't list angles, so any angle is allowed.
+        if has_column(conn, "products_angles", "angle") {
+            let angles: Vec<(u32, u32)> = query_table(
+                conn,
+                "products_angles",
+                "SELECT product_id, angle FROM products_angles",
+                &mut report,
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )?;
+            for (product_id, angle) in angles {
+                products_angles.entry(product_id).or_default().insert(angle);
+            }
+        }
+
+        let product_sizes = query_table(
+            conn,
+            "product_sizes",
+            "SELECT
+                id, product_id, edge_left, edge_right, edge_bottom, edge_top,
+                name, description, image_filename, position, is_listed
+            FROM product_sizes",
+            &mut report,
+            |row| {
+                Ok((
+                    row.get(0)?,
+                    ProductSize {
+                        id: row.get(0)?,
+                        product_id: row.get(1)?,
+                        edge_left: row.get(2)?,
+                        edge_right: row.get(3)?,
+                        edge_bottom: row.get(4)?,
+                        edge_top: row.get(5)?,
+                        name: row.get(6)?,
+                        description: row.get(7)?,
+                        image_filename: row.get(8)?,
+                        position: row.get(9)?,
+                        is_listed: row.get(10)?,
+                    },
+                ))
+            },
+        )?;
+
+        let layouts = query_table(
+            conn,
+            "layouts",
+            "SELECT
+                id, product_id, name, is_mirrored, is_listed
+            FROM layouts",
+            &mut report,
+            |row| {
+                Ok((
+                    row.get(0)?,
+                    Layout {
+                        id: row.get(0)?,
+                        product_id: row.get(1)?,
+                        name: row.get(2)?,
+                        is_mirrored: row.get(3)?,
+                        is_listed: row.get(4)?,
+                    },
+                ))
+            },
+        )?;
+
+        let sets = query_table(
+            conn,
+            "sets",
+            "SELECT
+                id, name, hsm
+            FROM sets",
+            &mut report,
+            |row| {
+                Ok((
+                    row.get(0)?,
+                    Set {
+                        id: row.get(0)?,
+                        name: row.get(1)?,
+                        hsm: row.get(2)?,
+                    },
+                ))
+            },
+        )?;
+
+        let product_sizes_layouts_sets = query_table(
+            conn,
+            "product_sizes_layouts_sets",
+            "SELECT
+                id, product_size_id, layout_id, set_id, image_filename, is_listed
+            FROM product_sizes_layouts_sets",
+            &mut report,
+            |row| {
+                Ok((
+                    row.get(0)?,
+                    ProductSizeLayoutSet {
+                        id: row.get(0)?,
+                        product_size_id: row.get(1)?,
+                        layout_id: row.get(2)?,
+                        set_id: row.get(3)?,
+                        image_filename: row.get(4)?,
+                        is_listed: row.get(5)?,
+                    },
+                ))
+            },
+        )?;
+
+        let leds = query_table(
+            conn,
+            "leds",
+            "SELECT
+                id, product_size_id, hole_id, position
+            FROM leds",
+            &mut report,
+            |row| {
+                Ok((
+                    row.get(0)?,
+                    Led {
+                        id: row.get(0)?,
+                        product_size_id: row.get(1)?,
+                        hole_id: row.get(2)?,
+                        position: row.get(3)?,
+                    },
+                ))
+            },
+        )?;
+
+        let mut climb_stats: HashMap<String, HashMap<u32, ClimbStats>> = HashMap::default();
+        let stats: Vec<(String, ClimbStats)> = query_table(
+            conn,
+            "climb_stats",
+            "SELECT
+                climb_uuid, angle, display_difficulty, benchmark_difficulty,
+                ascensionist_count, difficulty_average, quality_average
+            FROM climb_stats",
+            &mut report,
+            |row| {
+                Ok((
+                    row.get(0)?,
+                    ClimbStats {
+                        climb_uuid: row.get(0)?,
+                        angle: row.get(1)?,
+                        display_difficulty: row.get(2)?,
+                        benchmark_difficulty: row.get(3)?,
+                        ascensionist_count: row.get(4)?,
+                        difficulty_average: row.get(5)?,
+                        quality_average: row.get(6)?,
+                    },
+                ))
+            },
+        )?;
+        for (uuid, stats) in stats {
+            climb_stats
+                .entry(uuid)
+                .or_default()
+                .insert(stats.angle, stats);
+        }
+
+        let difficulty_grades = query_table(
+            conn,
+            "difficulty_grades",
+            "SELECT
+                difficulty, boulder_name, route_name, is_listed
+            FROM difficulty_grades",
+            &mut report,
+            |row| {
+                Ok((
+                    row.get(0)?,
+                    DifficultyGrade {
+                        difficulty: row.get(0)?,
+                        boulder_name: row.get(1)?,
+                        route_name: row.get(2)?,
+                        is_listed: row.get(3)?,
+                    },
+                ))
+            },
+        )?;
+
+        let mut kilter = Self {
+            holes,
+            placements,
+            placement_roles,
+            climbs,
+            products,
+            products_angles,
+            product_sizes,
+            layouts,
+            sets,
+            product_sizes_layouts_sets,
+            leds,
+            climb_stats,
+            difficulty_grades,
+            placement_climbs: HashMap::default(),
+            climb_text: HashMap::default(),
+            climb_sets: HashMap::default(),
+            layout_sets: HashMap::default(),
+            set_images: HashMap::default(),
+            led_positions: HashMap::default(),
+            placements_generation: 0,
+            climb_generations: HashMap::default(),
+            climbs_generation: 0,
+        };
+        kilter.rebuild_indices();
+
+        Ok((kilter, report))
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn json_update_files<P: AsRef<Path>>(
+        &mut self,
+        path: P,
+    ) -> Result<LoadReport, KilterDataError> {
+        let mut report = LoadReport::default();
+        let mut files: Vec<_> = read_dir(&path)?
+            .filter_map(Result::ok)
+            .filter_map(|entry| entry.file_name().into_string().ok())
+            .filter(|file_name| file_name.ends_with(".json"))
+            .collect();
+        // Later updates must be applied after earlier ones.
+        files.sort();
+
+        for file in files {
+            let file_path = path.as_ref().join(file);
+            report.extend(self.json_update_file(file_path)?);
+        }
+        Ok(report)
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn json_update_file<P: AsRef<Path>>(
+        &mut self,
+        path: P,
+    ) -> Result<LoadReport, KilterDataError> {
+        let file = File::open(path)?;
+        let reader = BufReader::new(file);
+        self.json_update_reader(reader)
+    }
+
+    /// Every climb by uuid, in the order they were added.
+    pub fn climbs(&self) -> &IndexMap<String, Climb> {
+        &self.climbs
+    }
+
+    pub fn climb(&self, uuid: &str) -> Option<&Climb> {
+        self.climbs.get(uuid)
+    }
+
+    /// The climb at `index` in [`KilterData::climbs`], with its uuid.
+    pub fn climb_at(&self, index: usize) -> Option<(&String, &Climb)> {
+        self.climbs.get_index(index)
+    }
+
+    /// The index of the climb with `uuid` in [`KilterData::climbs`].
+    pub fn climb_index(&self, uuid: &str) -> Option<usize> {
+        self.climbs.get_index_of(uuid)
+    }
+
+    /// Inserts a climb, or replaces the climb with the same uuid, and returns
+    /// its index.
+    pub fn insert_climb(&mut self, climb: Climb) -> usize {
+        self.record_climb_change(&climb.uuid);
+        self.climb_text
+            .insert(climb.uuid.clone(), ClimbText::new(&climb));
+        self.climb_sets
+            .insert(climb.uuid.clone(), self.find_climb_set_ids(&climb));
+        let (idx, old) = self.climbs.insert_full(climb.uuid.clone(), climb);
+
+        if let Some(old) = old {
+            for placement_id in old.frames.placement_ids() {
+                if let Some(climbs) = self.placement_climbs.get_mut(&placement_id) {
+                    if let Ok(pos) = climbs.binary_search(&idx) {
+                        climbs.remove(pos);
+                    }
+                }
+            }
+        }
+
+        for placement_id in self.climbs[idx].frames.placement_ids() {
+            let climbs = self.placement_climbs.entry(placement_id).or_default();
+            if let Err(pos) = climbs.binary_search(&idx) {
+                climbs.insert(pos, idx);
+            }
+        }
+
+        idx
+    }
+
+    /// Removes the climb with `uuid`. The indices of later climbs shift down.
+    pub fn remove_climb(&mut self, uuid: &str) -> Option<Climb> {
+        let climb = self.climbs.shift_remove(uuid)?;
+        self.record_climb_change(uuid);
+        self.climb_text.remove(uuid);
+        self.climb_sets.remove(uuid);
+        self.rebuild_placement_index();
+        Some(climb)
+    }
+
+    /// Removes every climb in `uuids`, rebuilding the placement index once
+    /// rather than for each climb.
+    pub fn remove_climbs(&mut self, uuids: &HashSet<String>) -> Vec<Climb> {
+        let mut removed = vec![];
+        self.climbs.retain(|uuid, climb| {
+            if !uuids.contains(uuid) {
+                return true;
+            }
+            removed.push(std::mem::take(climb));
+            false
+        });
+
+        if !removed.is_empty() {
+            for climb in &removed {
+                self.record_climb_change(&climb.uuid);
+                self.climb_text.remove(&climb.uuid);
+                self.climb_sets.remove(&climb.uuid);
+            }
+            self.rebuild_placement_index();
+        }
+
+        removed
+    }
+
+    /// Changes whenever placements, holes or product sizes might have, so that
+    /// what's built from where holds are doesn't have to be rebuilt each time
+    /// a climb changes.
+    pub fn placements_generation(&self) -> u64 {
+        self.placements_generation
+    }
+
+    /// The generation of the latest change to any climb since loading, to pass
+    /// to [`KilterData::climbs_changed_since`] later.
+    pub fn climbs_generation(&self) -> u64 {
+        self.climbs_generation
+    }
+
+    /// The uuids of climbs inserted or removed after `generation`, so that
+    /// what's kept per climb can be updated without going through them all.
+    pub fn climbs_changed_since(&self, generation: u64) -> impl Iterator<Item = &str> {
+        self.climb_generations
+            .iter()
+            .filter(move |(_, changed)| **changed > generation)
+            .map(|(uuid, _)| uuid.as_str())
+    }
+
+    fn record_climb_change(&mut self, uuid: &str) {
+        self.climbs_generation = NEXT_GENERATION.fetch_add(1, Ordering::Relaxed);
+        self.climb_generations
+            .insert(uuid.to_string(), self.climbs_generation);
+    }
+
+    fn bump_placements_generation(&mut self) {
+        self.placements_generation = NEXT_GENERATION.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Builds every index from scratch, after loading climbs directly.
+    fn rebuild_indices(&mut self) {
+        self.bump_placements_generation();
+        self.climb_text = self
+            .climbs
+            .values()
+            .map(|c| (c.uuid.clone(), ClimbText::new(c)))
+            .collect();
+        self.rebuild_placement_index();
+        self.rebuild_set_indices();
+        self.rebuild_led_index();
+    }
+
+    fn rebuild_led_index(&mut self) {
+        self.led_positions = self
+            .leds
+            .values()
+            .map(|l| ((l.product_size_id, l.hole_id), l.position))
+            .collect();
+    }
+
+    fn rebuild_placement_index(&mut self) {
+        self.placement_climbs.clear();
+        for (idx, climb) in self.climbs.values().enumerate() {
+            for placement_id in climb.frames.placement_ids() {
+                self.placement_climbs
+                    .entry(placement_id)
+                    .or_default()
+                    .push(idx);
+            }
+        }
+    }
+
+    /// Indices of climbs that use `placement_id` in any frame, in order.
+    pub fn climbs_with_placement(&self, placement_id: u32) -> &[usize] {
+        self.placement_climbs
+            .get(&placement_id)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    /// Fuzzy searches climb names, setters and descriptions, best matches
+    /// first.
+    pub fn search_by_name(&self, name: &str) -> Vec<(usize, &Climb)> {
+        self.query(&ClimbQuery {
+            name: name.to_string(),
+            ..default()
+        })
+    }
+
+    /// Returns the product that `layout_id` belongs to.
+    ///
+    /// Falls back to the product of the layout's placements' holes when the
+    /// `layouts` table wasn't loaded.
+    pub fn layout_product_id(&self, layout_id: u32) -> Option<u32> {
+        if let Some(layout) = self.layouts.get(&layout_id) {
+            return Some(layout.product_id);
+        }
+
+        self.placements
+            .values()
+            .find(|p| p.layout_id == layout_id)
+            .and_then(|p| self.holes.get(&p.hole_id))
+            .map(|h| h.product_id)
+    }
+
+    /// The position of `placement_id`'s LED on boards of `product_size_id`.
+    pub fn led_position(&self, product_size_id: u32, placement_id: u32) -> Option<u32> {
+        let placement = self.placements.get(&placement_id)?;
+
+        self.led_positions
+            .get(&(product_size_id, placement.hole_id))
+            .copied()
+    }
+
+    /// Layouts sorted by id.
+    pub fn sorted_layouts(&self) -> Vec<&Layout> {
+        let mut layouts: Vec<_> = self.layouts.values().collect();
+        layouts.sort_by_key(|l| l.id);
+        layouts
+    }
+
+    /// Product sizes available for `product_id`, sorted by their position.
+    pub fn sorted_product_sizes(&self, product_id: u32) -> Vec<&ProductSize> {
+        let mut sizes: Vec<_> = self
+            .product_sizes
+            .values()
+            .filter(|s| s.product_id == product_id)
+            .collect();
+        sizes.sort_by_key(|s| (s.position, s.id));
+        sizes
+    }
+
+    /// Placement roles available for `product_id`, sorted by their position.
+    pub fn sorted_placement_roles(&self, product_id: u32) -> Vec<&PlacementRole> {
+        let mut roles: Vec<_> = self
+            .placement_roles
+            .values()
+            .filter(|r| r.product_id == product_id)
+            .collect();
+        roles.sort_by_key(|r| (r.position, r.id));
+        roles
+    }
+
+    /// Whether `placement_id` is on `layout_id` and fits on the product size,
+    /// if one is given.
+    pub fn placement_on(
+        &self,
+        placement_id: u32,
+        layout_id: u32,
+        product_size_id: Option<u32>,
+    ) -> bool {
+        let Some(placement) = self.placements.get(&placement_id) else {
+            return false;
+        };
+
+        if placement.layout_id != layout_id {
+            return false;
+        }
+
+        let Some(size) = product_size_id.and_then(|id| self.product_sizes.get(&id)) else {
+            return true;
+        };
+
+        self.holes
+            .get(&placement.hole_id)
+            .is_some_and(|hole| size.contains_hole(hole))
+    }
+
+    /// Whether `climb` is on `layout_id` and fits on the product size, if one
+    /// is given.
+    pub fn climb_on(&self, climb: &Climb, layout_id: u32, product_size_id: Option<u32>) -> bool {
+        if climb.layout_id != layout_id {
+            return false;
+        }
+
+        let Some(size) = product_size_id.and_then(|id| self.product_sizes.get(&id)) else {
+            return true;
+        };
+
+        size.contains_climb(climb)
+    }
+
+    /// The angles the product of a layout can be set to, if they're known.
+    pub fn layout_angles(&self, layout_id: u32) -> Option<&BTreeSet<u32>> {
+        let layout = self.layouts.get(&layout_id)?;
+        self.products_angles
+            .get(&layout.product_id)
+            .filter(|angles| !angles.is_empty())
+    }
+
+    pub fn climb_stats(&self, uuid: &str, angle: u32) -> Option<&ClimbStats> {
+        self.climb_stats
+            .get(uuid)
+            .and_then(|stats| stats.get(&angle))
+    }
+
+    /// The grade nearest to a fractional `difficulty`.
+    pub fn grade(&self, difficulty: f32) -> Option<&DifficultyGrade> {
+        self.difficulty_grades
+            .get(&(difficulty.round().max(0.) as u32))
+    }
+}
+
+/// Runs `sql` and collects the rows decoded by `f`, skipping and reporting any
+/// that fail.
+#[cfg(not(any(target_arch = "wasm32", target_os = "android", target_os = "ios")))]
+fn query_table<K, T, C: Default + Extend<(K, T)>>(
+    conn: &Connection,
+    table: &'static str,
+    sql: &str,
+    report: &mut LoadReport,
+    f: impl FnMut(&Row) -> rusqlite::Result<(K, T)>,
+) -> Result<C, KilterDataError> {
+    let mut stmt = conn.prepare(sql).map_err(|e| KilterDataError::Schema {
+        table,
+        message: e.to_string(),
+    })?;
+
+    let mut out = C::default();
+    for (index, row) in stmt.query_map([], f)?.enumerate() {
+        match row {
+            Ok(item) => out.extend(Some(item)),
+            Err(e) => report.record_error(table, index, e),
+        }
+    }
+
+    Ok(out)
+}
+
+/// Opens a Kilter Board app database for reading.
+///
+/// Unlike [`Connection::open`], this fails when there's no database at `path`
+/// rather than creating an empty one.
+#[cfg(not(any(target_arch = "wasm32", target_os = "android", target_os = "ios")))]
+pub fn open_database(path: &str) -> Result<Connection, KilterDataError> {
+    std::fs::metadata(path)?;
+
+    Ok(Connection::open_with_flags(
+        path,
+        OpenFlags::SQLITE_OPEN_READ_ONLY,
+    )?)
+}
+
+/// Opens a copy of a database held in memory, read only.
+#[cfg(not(any(target_arch = "wasm32", target_os = "android", target_os = "ios")))]
+fn deserialize_database(bytes: &[u8]) -> Result<Connection, KilterDataError> {
+    use rusqlite::{ffi, serialize::OwnedData, DatabaseName};
+    use std::ptr::NonNull;
+
+    let mut conn = Connection::open_in_memory()?;
+    // SAFETY: SQLite takes ownership of the buffer, so it must come from
+    // `sqlite3_malloc`. It's only written to before being handed over.
+    let data = unsafe {
+        let ptr = ffi::sqlite3_malloc64(bytes.len().max(1) as u64).cast::<u8>();
+        let ptr = NonNull::new(ptr).ok_or(rusqlite::Error::SqliteFailure(
+            ffi::Error::new(ffi::SQLITE_NOMEM),
+            None,
+        ))?;
+        std::ptr::copy_nonoverlapping(bytes.as_ptr(), ptr.as_ptr(), bytes.len());
+        OwnedData::from_raw_nonnull(ptr, bytes.len())
+    };
+    conn.deserialize(DatabaseName::Main, data, true)?;
+    Ok(conn)
+}
+
+#[cfg(not(any(target_arch = "wasm32", target_os = "android", target_os = "ios")))]
+fn has_column(conn: &Connection, table: &str, column: &str) -> bool {
+    conn.prepare(&format!("SELECT {column} FROM {table} LIMIT 0"))
+        .is_ok()
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Hole {
+    pub id: u32,
+    pub product_id: u32,
+    pub name: String,
+    pub x: i32,
+    pub y: i32,
+    pub mirrored_hole_id: u32,
+    pub mirror_group: u32,
+}
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Placement {
+    pub id: u32,
+    pub layout_id: u32,
+    pub hole_id: u32,
+    pub set_id: u32,
+    //pub hold_id: u32,
+    //pub rotation: u32,
+    pub default_placement_role_id: Option<u32>,
+}
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PlacementRole {
+    pub id: u32,
+    pub product_id: u32,
+    pub position: u32,
+    #[serde(default)]
+    pub min_count_in_climb: Option<u32>,
+    #[serde(default)]
+    pub max_count_in_climb: Option<u32>,
+    pub name: String,
+    pub full_name: String,
+    pub led_color: String,
+    pub screen_color: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Product {
+    pub id: u32,
+    pub name: String,
+    pub is_listed: bool,
+}
+/// An angle a product can be set to.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ProductAngle {
+    pub product_id: u32,
+    pub angle: u32,
+}
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ProductSize {
+    pub id: u32,
+    pub product_id: u32,
+    pub edge_left: i32,
+    pub edge_right: i32,
+    pub edge_bottom: i32,
+    pub edge_top: i32,
+    pub name: String,
+    pub description: String,
+    pub image_filename: Option<String>,
+    pub position: u32,
+    pub is_listed: bool,
+}
+impl ProductSize {
+    pub fn contains_hole(&self, hole: &Hole) -> bool {
+        hole.x > self.edge_left
+            && hole.x < self.edge_right
+            && hole.y > self.edge_bottom
+            && hole.y < self.edge_top
+    }
+
+    pub fn contains_climb(&self, climb: &Climb) -> bool {
+        // Climbs authored in-app don't have their edges computed.
+        if climb.edge_left == 0
+            && climb.edge_right == 0
+            && climb.edge_bottom == 0
+            && climb.edge_top == 0
+        {
+            return true;
+        }
+
+        climb.edge_left > self.edge_left
+            && climb.edge_right < self.edge_right
+            && climb.edge_bottom > self.edge_bottom
+            && climb.edge_top < self.edge_top
+    }
+}
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Layout {
+    pub id: u32,
+    pub product_id: u32,
+    pub name: String,
+    pub is_mirrored: bool,
+    pub is_listed: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Set {
+    pub id: u32,
+    pub name: String,
+    pub hsm: u32,
+}
+/// A set on a layout at a product size.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ProductSizeLayoutSet {
+    pub id: u32,
+    pub product_size_id: u32,
+    pub layout_id: u32,
+    pub set_id: u32,
+    /// An image of the set's holds with a transparent background, from the
+    /// Kilter Board app.
+    pub image_filename: Option<String>,
+    pub is_listed: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Led {
+    pub id: u32,
+    pub product_size_id: u32,
+    pub hole_id: u32,
+    pub position: u32,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ClimbStats {
+    pub climb_uuid: String,
+    pub angle: u32,
+    pub display_difficulty: f32,
+    pub benchmark_difficulty: Option<f32>,
+    pub ascensionist_count: u32,
+    pub difficulty_average: f32,
+    pub quality_average: f32,
+}
+impl ClimbStats {
+    pub fn is_benchmark(&self) -> bool {
+        self.benchmark_difficulty.is_some()
+    }
+
+    /// The benchmark difficulty if there is one, or the displayed difficulty.
+    pub fn difficulty(&self) -> f32 {
+        self.benchmark_difficulty.unwrap_or(self.display_difficulty)
+    }
+}
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct DifficultyGrade {
+    pub difficulty: u32,
+    /// e.g. `6a/V3`
+    pub boulder_name: String,
+    pub route_name: String,
+    pub is_listed: bool,
+}
+
+#[derive(Serialize, Deserialize, Default, Debug, Clone)]
+pub struct Climb {
+    pub uuid: String,
+    pub name: String,
+    pub description: String,
+    pub hsm: u32,
+    pub edge_left: i32,
+    pub edge_right: i32,
+    pub edge_bottom: i32,
+    pub edge_top: i32,
+    pub frames_count: u32,
+    pub frames_pace: u32,
+    pub frames: Frames,
+    pub setter_id: u32,
+    pub setter_username: String,
+    pub layout_id: u32,
+    pub is_draft: bool,
+    pub is_listed: bool,
+    pub angle: Option<u32>,
+    /// e.g. `2021-06-06 18:56:54.419261`. Empty for climbs created in-app.
+    #[serde(default)]
+    pub created_at: String,
+    /// Not part of the climb's data, so it isn't sent when publishing.
+    #[serde(skip)]
+    pub origin: ClimbOrigin,
+}
+impl Climb {
+    /// Marks a climb as edited locally, keeping its origin if it was already
+    /// local.
+    pub fn mark_edited(&mut self) {
+        if self.origin == ClimbOrigin::Database {
+            self.origin = ClimbOrigin::Edited;
+        }
+    }
+
+    /// How long each frame of a multi-frame climb is shown for.
+    ///
+    /// `frames_pace` is treated as frames per minute, with `0` meaning one
+    /// frame per second.
+    pub fn frame_duration(&self) -> Duration {
+        if self.frames_pace == 0 {
+            return Duration::from_secs(1);
+        }
+
+        Duration::from_secs_f32(60. / self.frames_pace as f32)
+    }
+}
+
+/// Where a climb came from. Climbs that didn't come from the database are
+/// saved to the user's local store.
+#[derive(Serialize, Deserialize, Default, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ClimbOrigin {
+    #[default]
+    Database,
+    /// Created with the New button.
+    Authored,
+    Pasted,
+    Generated,
+    /// A database climb that was changed in-app.
+    Edited,
+}
+impl ClimbOrigin {
+    pub fn is_local(&self) -> bool {
+        *self != Self::Database
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Database => "Database",
+            Self::Authored => "Authored",
+            Self::Pasted => "Pasted",
+            Self::Generated => "Generated",
+            Self::Edited => "Edited",
+        }
+    }
+}
+
+#[cfg(all(
+    test,
+    not(any(target_arch = "wasm32", target_os = "android", target_os = "ios"))
+))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn deserialize_database_reads_copied_bytes() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch("CREATE TABLE t (x INTEGER); INSERT INTO t VALUES (7);")
+            .unwrap();
+        let bytes = conn.serialize(rusqlite::DatabaseName::Main).unwrap();
+
+        let copy = deserialize_database(&bytes).unwrap();
+        let x: u32 = copy
+            .query_row("SELECT x FROM t", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(x, 7);
+        assert!(copy.execute("INSERT INTO t VALUES (8)", []).is_err());
+    }
+}
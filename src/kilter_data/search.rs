@@ -0,0 +1,495 @@
+use std::cmp::Ordering;
+
+use bevy::platform::collections::HashSet;
+
+use super::{fuzzy::FuzzyQuery, Climb, ClimbStats, KilterData};
+
+/// A placement to search for, optionally only with a particular role.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HoldFilter {
+    pub placement_id: u32,
+    pub role_id: Option<u32>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum HoldMatch {
+    /// Climbs using every hold.
+    #[default]
+    All,
+    /// Climbs using at least one hold.
+    Any,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ClimbSort {
+    /// Database order.
+    #[default]
+    None,
+    /// Most ascents first.
+    Popularity,
+    /// Easiest first.
+    Grade,
+    /// Highest quality first.
+    Quality,
+    /// Most recently created first.
+    Newest,
+}
+impl ClimbSort {
+    pub const ALL: [Self; 5] = [
+        Self::None,
+        Self::Popularity,
+        Self::Grade,
+        Self::Quality,
+        Self::Newest,
+    ];
+
+    pub fn next(&self) -> Self {
+        let pos = Self::ALL.iter().position(|s| s == self).unwrap_or(0);
+        Self::ALL[(pos + 1) % Self::ALL.len()]
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::None => "Default",
+            Self::Popularity => "Popular",
+            Self::Grade => "Grade",
+            Self::Quality => "Quality",
+            Self::Newest => "Newest",
+        }
+    }
+}
+
+/// Filters and sorting for [`KilterData::query`].
+///
+/// Grade, quality, ascent and benchmark filters use the stats at `angle`, and
+/// exclude climbs without stats at that angle.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ClimbQuery {
+    /// Fuzzy matches the climb's name, setter and description, or its index.
+    /// Results are ranked by how well they match unless `sort` is set.
+    pub name: String,
+    pub angle: u32,
+    pub min_difficulty: Option<u32>,
+    pub max_difficulty: Option<u32>,
+    pub min_quality: Option<f32>,
+    pub min_ascents: Option<u32>,
+    pub benchmark_only: bool,
+    /// Matches the setter's username, ignoring case.
+    pub setter: Option<String>,
+    /// Matches the angle the climb was set at.
+    pub setter_angle: Option<u32>,
+    pub is_listed: Option<bool>,
+    pub is_draft: Option<bool>,
+    /// Includes unlisted and draft climbs from the database, which are left
+    /// out by default unless `is_listed` or `is_draft` asks for them. The
+    /// user's own climbs are always included.
+    pub include_hidden: bool,
+    pub layout_id: Option<u32>,
+    pub product_size_id: Option<u32>,
+    /// Excludes climbs using holds from these sets, e.g. sets that aren't
+    /// installed.
+    pub excluded_sets: HashSet<u32>,
+    /// Only climbs using these holds, see [`KilterData::climbs_with_holds`].
+    pub holds: Vec<HoldFilter>,
+    pub hold_match: HoldMatch,
+    pub sort: ClimbSort,
+}
+impl ClimbQuery {
+    /// Whether any filter that depends on stats is set.
+    fn needs_stats(&self) -> bool {
+        self.min_difficulty.is_some()
+            || self.max_difficulty.is_some()
+            || self.min_quality.is_some()
+            || self.min_ascents.is_some()
+            || self.benchmark_only
+    }
+
+    fn matches(&self, climb: &Climb, stats: Option<&ClimbStats>) -> bool {
+        if let Some(setter) = &self.setter {
+            if !climb.setter_username.eq_ignore_ascii_case(setter) {
+                return false;
+            }
+        }
+
+        if self.setter_angle.is_some_and(|a| climb.angle != Some(a)) {
+            return false;
+        }
+
+        let hidden = !climb.is_listed || climb.is_draft;
+        if hidden
+            && !self.include_hidden
+            && !climb.origin.is_local()
+            && self.is_listed.is_none()
+            && self.is_draft.is_none()
+        {
+            return false;
+        }
+
+        if self.is_listed.is_some_and(|l| climb.is_listed != l) {
+            return false;
+        }
+
+        if self.is_draft.is_some_and(|d| climb.is_draft != d) {
+            return false;
+        }
+
+        if !self.needs_stats() {
+            return true;
+        }
+
+        let Some(stats) = stats else {
+            return false;
+        };
+
+        let difficulty = stats.difficulty().round();
+
+        if self
+            .min_difficulty
+            .is_some_and(|min| difficulty < min as f32)
+        {
+            return false;
+        }
+
+        if self
+            .max_difficulty
+            .is_some_and(|max| difficulty > max as f32)
+        {
+            return false;
+        }
+
+        if self
+            .min_quality
+            .is_some_and(|min| stats.quality_average < min)
+        {
+            return false;
+        }
+
+        if self
+            .min_ascents
+            .is_some_and(|min| stats.ascensionist_count < min)
+        {
+            return false;
+        }
+
+        if self.benchmark_only && !stats.is_benchmark() {
+            return false;
+        }
+
+        true
+    }
+}
+
+impl KilterData {
+    /// Returns the indices of climbs using `holds`, in order.
+    ///
+    /// Uses an index by placement, so this stays fast with many climbs.
+    pub fn climbs_with_holds(&self, holds: &[HoldFilter], hold_match: HoldMatch) -> Vec<usize> {
+        let mut postings: Vec<&[usize]> = holds
+            .iter()
+            .map(|h| self.climbs_with_placement(h.placement_id))
+            .collect();
+
+        let candidates: Vec<usize> = match hold_match {
+            HoldMatch::All => {
+                // Start with the rarest placement to keep the intersection small.
+                postings.sort_by_key(|p| p.len());
+                let Some((first, rest)) = postings.split_first() else {
+                    return vec![];
+                };
+                first
+                    .iter()
+                    .copied()
+                    .filter(|idx| rest.iter().all(|p| p.binary_search(idx).is_ok()))
+                    .collect()
+            }
+            HoldMatch::Any => {
+                let mut all: Vec<usize> = postings.concat();
+                all.sort_unstable();
+                all.dedup();
+                all
+            }
+        };
+
+        if holds.iter().all(|h| h.role_id.is_none()) {
+            return candidates;
+        }
+
+        candidates
+            .into_iter()
+            .filter(|idx| {
+                let Some((_, climb)) = self.climbs.get_index(*idx) else {
+                    return false;
+                };
+                let mut matches = holds
+                    .iter()
+                    .map(|h| climb.frames.contains(h.placement_id, h.role_id));
+                match hold_match {
+                    HoldMatch::All => matches.all(|m| m),
+                    HoldMatch::Any => matches.any(|m| m),
+                }
+            })
+            .collect()
+    }
+
+    /// Returns the index and climb of every climb matching `query`, sorted by
+    /// `query.sort`, or by how well they match `query.name` if there's no sort.
+    pub fn query(&self, query: &ClimbQuery) -> Vec<(usize, &Climb)> {
+        let candidates: Box<dyn Iterator<Item = (usize, &Climb)>> = if query.holds.is_empty() {
+            Box::new(self.climbs.values().enumerate())
+        } else {
+            Box::new(
+                self.climbs_with_holds(&query.holds, query.hold_match)
+                    .into_iter()
+                    .filter_map(|idx| self.climbs.get_index(idx).map(|(_, c)| (idx, c))),
+            )
+        };
+
+        let fuzzy = FuzzyQuery::new(&query.name);
+
+        let mut results: Vec<_> = candidates
+            .filter(|(_, climb)| {
+                query
+                    .layout_id
+                    .is_none_or(|layout_id| self.climb_on(climb, layout_id, query.product_size_id))
+            })
+            .filter(|(_, climb)| !self.climb_uses_sets(&climb.uuid, &query.excluded_sets))
+            .map(|(idx, climb)| (idx, climb, self.climb_stats(&climb.uuid, query.angle)))
+            .filter(|(_, climb, stats)| query.matches(climb, *stats))
+            .filter_map(|(idx, climb, stats)| {
+                let text = self.climb_text.get(&climb.uuid)?;
+                fuzzy
+                    .score(idx, text)
+                    .map(|score| (idx, climb, stats, score))
+            })
+            .collect();
+
+        // Climbs without stats sort after those with them.
+        fn by_stat(a: Option<f32>, b: Option<f32>, descending: bool) -> Ordering {
+            match (a, b) {
+                (Some(a), Some(b)) if descending => b.total_cmp(&a),
+                (Some(a), Some(b)) => a.total_cmp(&b),
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (None, None) => Ordering::Equal,
+            }
+        }
+
+        match query.sort {
+            ClimbSort::None if fuzzy.is_empty() => {}
+            ClimbSort::None => results.sort_by(|(_, _, _, a), (_, _, _, b)| b.cmp(a)),
+            ClimbSort::Popularity => results.sort_by(|(_, _, a, _), (_, _, b, _)| {
+                by_stat(
+                    a.map(|s| s.ascensionist_count as f32),
+                    b.map(|s| s.ascensionist_count as f32),
+                    true,
+                )
+            }),
+            ClimbSort::Grade => results.sort_by(|(_, _, a, _), (_, _, b, _)| {
+                by_stat(a.map(|s| s.difficulty()), b.map(|s| s.difficulty()), false)
+            }),
+            ClimbSort::Quality => results.sort_by(|(_, _, a, _), (_, _, b, _)| {
+                by_stat(
+                    a.map(|s| s.quality_average),
+                    b.map(|s| s.quality_average),
+                    true,
+                )
+            }),
+            ClimbSort::Newest => {
+                results.sort_by(|(_, a, _, _), (_, b, _, _)| b.created_at.cmp(&a.created_at))
+            }
+        }
+
+        results
+            .into_iter()
+            .map(|(idx, climb, _, _)| (idx, climb))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::kilter_data::ClimbOrigin;
+
+    fn climb(uuid: &str, frames: &str) -> Climb {
+        Climb {
+            uuid: uuid.to_string(),
+            name: uuid.to_string(),
+            frames: frames.parse().unwrap(),
+            ..Default::default()
+        }
+    }
+
+    fn hold(placement_id: u32, role_id: Option<u32>) -> HoldFilter {
+        HoldFilter {
+            placement_id,
+            role_id,
+        }
+    }
+
+    fn kilter(climbs: &[(&str, &str)]) -> KilterData {
+        let mut kilter = KilterData::default();
+        for (uuid, frames) in climbs {
+            kilter.insert_climb(climb(uuid, frames));
+        }
+        kilter
+    }
+
+    fn uuids(kilter: &KilterData, indices: Vec<usize>) -> Vec<&str> {
+        indices
+            .into_iter()
+            .map(|idx| kilter.climb_at(idx).unwrap().0.as_str())
+            .collect()
+    }
+
+    /// Checks the placement index against a search of every climb.
+    fn assert_index_matches(kilter: &KilterData) {
+        for placement_id in 1..=5 {
+            let expected: Vec<usize> = kilter
+                .climbs()
+                .values()
+                .enumerate()
+                .filter(|(_, c)| c.frames.contains(placement_id, None))
+                .map(|(idx, _)| idx)
+                .collect();
+
+            assert_eq!(
+                kilter.climbs_with_placement(placement_id),
+                expected,
+                "placement {placement_id}"
+            );
+        }
+    }
+
+    #[test]
+    fn matches_all_or_any_holds() {
+        let kilter = kilter(&[("a", "p1r12p2r13"), ("b", "p2r12p3r14"), ("c", "p4r15")]);
+
+        let holds = [hold(1, None), hold(2, None)];
+        assert_eq!(
+            uuids(&kilter, kilter.climbs_with_holds(&holds, HoldMatch::All)),
+            ["a"]
+        );
+        assert_eq!(
+            uuids(&kilter, kilter.climbs_with_holds(&holds, HoldMatch::Any)),
+            ["a", "b"]
+        );
+
+        assert!(kilter.climbs_with_holds(&[], HoldMatch::All).is_empty());
+        assert!(kilter
+            .climbs_with_holds(&[hold(5, None)], HoldMatch::Any)
+            .is_empty());
+    }
+
+    #[test]
+    fn filters_holds_by_role() {
+        let kilter = kilter(&[("a", "p1r12p2r13"), ("b", "p2r12p3r14")]);
+
+        assert_eq!(
+            uuids(
+                &kilter,
+                kilter.climbs_with_holds(&[hold(2, Some(12))], HoldMatch::All)
+            ),
+            ["b"]
+        );
+        assert_eq!(
+            uuids(
+                &kilter,
+                kilter.climbs_with_holds(&[hold(1, Some(12)), hold(3, Some(14))], HoldMatch::Any)
+            ),
+            ["a", "b"]
+        );
+        assert!(kilter
+            .climbs_with_holds(&[hold(1, Some(12)), hold(2, Some(12))], HoldMatch::All)
+            .is_empty());
+    }
+
+    #[test]
+    fn leaves_out_hidden_climbs_by_default() {
+        let mut kilter = KilterData::default();
+        for (uuid, is_listed, is_draft, origin) in [
+            ("listed", true, false, ClimbOrigin::Database),
+            ("unlisted", false, false, ClimbOrigin::Database),
+            ("draft", true, true, ClimbOrigin::Database),
+            ("mine", false, true, ClimbOrigin::Authored),
+        ] {
+            kilter.insert_climb(Climb {
+                is_listed,
+                is_draft,
+                origin,
+                ..climb(uuid, "p1r12")
+            });
+        }
+
+        let uuids = |query: ClimbQuery| -> Vec<String> {
+            kilter
+                .query(&query)
+                .into_iter()
+                .map(|(_, c)| c.uuid.clone())
+                .collect()
+        };
+
+        assert_eq!(uuids(ClimbQuery::default()), ["listed", "mine"]);
+        assert_eq!(
+            uuids(ClimbQuery {
+                include_hidden: true,
+                ..Default::default()
+            }),
+            ["listed", "unlisted", "draft", "mine"]
+        );
+        assert_eq!(
+            uuids(ClimbQuery {
+                is_draft: Some(true),
+                ..Default::default()
+            }),
+            ["draft", "mine"]
+        );
+    }
+
+    #[test]
+    fn index_stays_correct_after_changes() {
+        let mut kilter = kilter(&[("a", "p1r12p2r13"), ("b", "p2r12p3r14"), ("c", "p4r15")]);
+        assert_index_matches(&kilter);
+
+        // Replacing a climb moves it off the placements it no longer uses.
+        kilter.insert_climb(climb("a", "p3r12p5r13"));
+        assert_index_matches(&kilter);
+
+        // Removing a climb shifts the indices of those after it.
+        kilter.remove_climb("a");
+        assert_index_matches(&kilter);
+        assert_eq!(kilter.climb_index("c"), Some(1));
+
+        let d = serde_json::to_value(climb("d", "p1r12p4r14")).unwrap();
+        let b = serde_json::to_value(climb("b", "p5r12")).unwrap();
+        kilter
+            .json_update(&json!({
+                "PUT": { "climbs": [d, b] },
+                "DELETE": { "climbs": ["c"] },
+            }))
+            .unwrap();
+        assert_index_matches(&kilter);
+        assert_eq!(kilter.climbs().keys().collect::<Vec<_>>(), ["b", "d"]);
+    }
+
+    #[test]
+    fn tracks_climbs_changed_since_a_generation() {
+        let mut kilter = kilter(&[("a", "p1r12"), ("b", "p2r12")]);
+        let changed = |kilter: &KilterData, generation| {
+            let mut uuids: Vec<_> = kilter.climbs_changed_since(generation).collect();
+            uuids.sort();
+            uuids.into_iter().map(str::to_string).collect::<Vec<_>>()
+        };
+        assert_eq!(changed(&kilter, 0), ["a", "b"]);
+
+        let generation = kilter.climbs_generation();
+        assert!(changed(&kilter, generation).is_empty());
+
+        kilter.insert_climb(climb("c", "p3r12"));
+        kilter.remove_climb("a");
+        assert_eq!(changed(&kilter, generation), ["a", "c"]);
+        assert!(kilter.climbs_generation() > generation);
+    }
+}
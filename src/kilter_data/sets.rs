@@ -0,0 +1,156 @@
+use bevy::platform::collections::{HashMap, HashSet};
+
+use super::{Climb, KilterData};
+
+impl KilterData {
+    /// The sets that placements on `layout_id` belong to, sorted by id.
+    pub fn layout_set_ids(&self, layout_id: u32) -> &[u32] {
+        self.layout_sets
+            .get(&layout_id)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    /// The set's name, e.g. `Bolt Ons`, or its id if the `sets` table wasn't
+    /// loaded.
+    pub fn set_name(&self, set_id: u32) -> String {
+        self.sets
+            .get(&set_id)
+            .map(|s| s.name.clone())
+            .unwrap_or_else(|| format!("Set {set_id}"))
+    }
+
+    /// The image of `set_id`'s holds on `layout_id` at `product_size_id`.
+    pub fn set_image(&self, product_size_id: u32, layout_id: u32, set_id: u32) -> Option<&str> {
+        self.set_images
+            .get(&(product_size_id, layout_id, set_id))
+            .map(String::as_str)
+    }
+
+    /// Whether any set on `layout_id` has an image at `product_size_id`.
+    pub fn has_set_images(&self, product_size_id: u32, layout_id: u32) -> bool {
+        self.layout_set_ids(layout_id)
+            .iter()
+            .any(|&set_id| self.set_image(product_size_id, layout_id, set_id).is_some())
+    }
+
+    /// The sets the climb with `uuid` uses, sorted by id.
+    pub fn climb_set_ids(&self, uuid: &str) -> &[u32] {
+        self.climb_sets
+            .get(uuid)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    /// Whether the climb with `uuid` uses any of `set_ids`.
+    pub fn climb_uses_sets(&self, uuid: &str, set_ids: &HashSet<u32>) -> bool {
+        !set_ids.is_empty()
+            && self
+                .climb_set_ids(uuid)
+                .iter()
+                .any(|id| set_ids.contains(id))
+    }
+
+    pub(super) fn find_climb_set_ids(&self, climb: &Climb) -> Vec<u32> {
+        let mut set_ids: Vec<_> = climb
+            .frames
+            .placement_ids()
+            .into_iter()
+            .filter_map(|id| self.placements.get(&id))
+            .map(|p| p.set_id)
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .collect();
+        set_ids.sort();
+        set_ids
+    }
+
+    /// Rebuilds the sets of every layout and climb, and the index of set
+    /// images, after placements or set images change.
+    pub(super) fn rebuild_set_indices(&mut self) {
+        let mut layout_sets: HashMap<u32, HashSet<u32>> = HashMap::default();
+        for placement in self.placements.values() {
+            layout_sets
+                .entry(placement.layout_id)
+                .or_default()
+                .insert(placement.set_id);
+        }
+        self.layout_sets = layout_sets
+            .into_iter()
+            .map(|(layout_id, set_ids)| {
+                let mut set_ids: Vec<_> = set_ids.into_iter().collect();
+                set_ids.sort();
+                (layout_id, set_ids)
+            })
+            .collect();
+
+        self.set_images = self
+            .product_sizes_layouts_sets
+            .values()
+            .filter(|s| s.is_listed)
+            .filter_map(|s| {
+                let image = s.image_filename.clone()?;
+                Some(((s.product_size_id, s.layout_id, s.set_id), image))
+            })
+            .collect();
+
+        self.climb_sets = self
+            .climbs
+            .values()
+            .map(|c| (c.uuid.clone(), self.find_climb_set_ids(c)))
+            .collect();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn placement(id: u32, set_id: u32) -> serde_json::Value {
+        json!({ "id": id, "layout_id": 1, "hole_id": id, "set_id": set_id })
+    }
+
+    #[test]
+    fn indexes_sets_of_climbs_and_layouts() {
+        let mut kilter = KilterData::default();
+        let climb = Climb {
+            uuid: "a".to_string(),
+            frames: "p1r12p3r13".parse().unwrap(),
+            ..Default::default()
+        };
+        let climb = serde_json::to_value(climb).unwrap();
+
+        // Climbs are applied before the placements they use.
+        kilter
+            .json_update(&json!({
+                "PUT": {
+                    "climbs": [climb],
+                    "placements": [placement(1, 20), placement(2, 21), placement(3, 22)],
+                    "product_sizes_layouts_sets": [
+                        { "id": 1, "product_size_id": 10, "layout_id": 1, "set_id": 20,
+                          "image_filename": "20.png", "is_listed": true },
+                        { "id": 2, "product_size_id": 10, "layout_id": 1, "set_id": 21,
+                          "image_filename": "21.png", "is_listed": false },
+                    ],
+                },
+            }))
+            .unwrap();
+
+        assert_eq!(kilter.layout_set_ids(1), [20, 21, 22]);
+        assert_eq!(kilter.climb_set_ids("a"), [20, 22]);
+        assert!(kilter.climb_uses_sets("a", &HashSet::from_iter([22])));
+        assert!(!kilter.climb_uses_sets("a", &HashSet::from_iter([21])));
+
+        assert_eq!(kilter.set_image(10, 1, 20), Some("20.png"));
+        assert_eq!(kilter.set_image(10, 1, 21), None);
+        assert!(kilter.has_set_images(10, 1));
+        assert!(!kilter.has_set_images(11, 1));
+
+        kilter
+            .json_update(&json!({ "PATCH": { "placements": [{ "id": 3, "set_id": 21 }] } }))
+            .unwrap();
+        assert_eq!(kilter.climb_set_ids("a"), [20, 21]);
+    }
+}
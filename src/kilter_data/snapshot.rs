@@ -0,0 +1,355 @@
+//! A compact binary snapshot of [`KilterData`] that's much quicker to load
+//! than the database and JSON updates, and works on every platform.
+//!
+//! A snapshot is [`MAGIC`], then [`SNAPSHOT_VERSION`] as a little-endian
+//! `u16`, then the tables encoded with bincode.
+
+use bevy::platform::collections::HashMap;
+use bincode::Options;
+use serde_derive::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+#[cfg(not(target_arch = "wasm32"))]
+use std::path::Path;
+
+use super::{
+    Climb, ClimbStats, DifficultyGrade, Hole, KilterData, KilterDataError, Layout, Led, Placement,
+    PlacementRole, Product, ProductAngle, ProductSize, ProductSizeLayoutSet, Set,
+};
+
+/// Starts every snapshot.
+pub const MAGIC: &[u8; 8] = b"KBSNAPSH";
+
+/// The version of the snapshot format. This must be bumped whenever any of
+/// the records in [`Tables`] change, so that older snapshots are rejected
+/// rather than misread.
+pub const SNAPSHOT_VERSION: u16 = 2;
+
+/// The length of [`MAGIC`] and the version, which is enough for
+/// [`check_snapshot`] to tell whether the rest can be read.
+pub const SNAPSHOT_HEADER_LEN: usize = MAGIC.len() + 2;
+
+/// Every table, in a form that's quick to encode and decode.
+#[derive(Serialize, Deserialize)]
+struct Tables {
+    holes: Vec<Hole>,
+    placements: Vec<Placement>,
+    placement_roles: Vec<PlacementRole>,
+    climbs: Vec<Climb>,
+    products: Vec<Product>,
+    products_angles: Vec<ProductAngle>,
+    product_sizes: Vec<ProductSize>,
+    layouts: Vec<Layout>,
+    sets: Vec<Set>,
+    product_sizes_layouts_sets: Vec<ProductSizeLayoutSet>,
+    leds: Vec<Led>,
+    climb_stats: Vec<ClimbStats>,
+    difficulty_grades: Vec<DifficultyGrade>,
+}
+
+/// Checks that `bytes` is a snapshot this version can read, returning the
+/// encoded tables.
+pub fn check_snapshot(bytes: &[u8]) -> Result<&[u8], KilterDataError> {
+    let Some(rest) = bytes.strip_prefix(MAGIC.as_slice()) else {
+        return Err(KilterDataError::NotASnapshot);
+    };
+
+    let Some((version, tables)) = rest.split_first_chunk::<2>() else {
+        return Err(KilterDataError::NotASnapshot);
+    };
+
+    let version = u16::from_le_bytes(*version);
+    if version != SNAPSHOT_VERSION {
+        return Err(KilterDataError::SnapshotVersion {
+            found: version,
+            supported: SNAPSHOT_VERSION,
+        });
+    }
+
+    Ok(tables)
+}
+
+impl KilterData {
+    /// Decodes a snapshot written by [`KilterData::to_snapshot`].
+    ///
+    /// Snapshots from other versions, and malformed or truncated ones, are
+    /// rejected with an error.
+    pub fn from_snapshot(bytes: &[u8]) -> Result<Self, KilterDataError> {
+        let tables = check_snapshot(bytes)?;
+
+        // A valid snapshot never decodes to more than it contains, so this
+        // stops a corrupt length from allocating too much.
+        let tables: Tables = bincode::DefaultOptions::new()
+            .with_limit(tables.len() as u64)
+            .deserialize(tables)?;
+
+        let mut climb_stats: HashMap<String, HashMap<u32, ClimbStats>> = HashMap::default();
+        for stats in tables.climb_stats {
+            climb_stats
+                .entry(stats.climb_uuid.clone())
+                .or_default()
+                .insert(stats.angle, stats);
+        }
+
+        let mut products_angles: HashMap<u32, BTreeSet<u32>> = HashMap::default();
+        for ProductAngle { product_id, angle } in tables.products_angles {
+            products_angles.entry(product_id).or_default().insert(angle);
+        }
+
+        let mut kilter = Self {
+            holes: tables.holes.into_iter().map(|h| (h.id, h)).collect(),
+            placements: tables.placements.into_iter().map(|p| (p.id, p)).collect(),
+            placement_roles: tables
+                .placement_roles
+                .into_iter()
+                .map(|r| (r.id, r))
+                .collect(),
+            climbs: tables
+                .climbs
+                .into_iter()
+                .map(|c| (c.uuid.clone(), c))
+                .collect(),
+            products: tables.products.into_iter().map(|p| (p.id, p)).collect(),
+            products_angles,
+            product_sizes: tables
+                .product_sizes
+                .into_iter()
+                .map(|s| (s.id, s))
+                .collect(),
+            layouts: tables.layouts.into_iter().map(|l| (l.id, l)).collect(),
+            sets: tables.sets.into_iter().map(|s| (s.id, s)).collect(),
+            product_sizes_layouts_sets: tables
+                .product_sizes_layouts_sets
+                .into_iter()
+                .map(|s| (s.id, s))
+                .collect(),
+            leds: tables.leds.into_iter().map(|l| (l.id, l)).collect(),
+            climb_stats,
+            difficulty_grades: tables
+                .difficulty_grades
+                .into_iter()
+                .map(|g| (g.difficulty, g))
+                .collect(),
+            placement_climbs: HashMap::default(),
+            climb_text: HashMap::default(),
+            climb_sets: HashMap::default(),
+            layout_sets: HashMap::default(),
+            set_images: HashMap::default(),
+            led_positions: HashMap::default(),
+            placements_generation: 0,
+            climb_generations: HashMap::default(),
+            climbs_generation: 0,
+        };
+        kilter.rebuild_indices();
+
+        Ok(kilter)
+    }
+
+    /// Encodes a snapshot of the data from the database, leaving out climbs
+    /// that are only stored locally.
+    pub fn to_snapshot(&self) -> Result<Vec<u8>, KilterDataError> {
+        let tables = Tables {
+            holes: self.holes.values().cloned().collect(),
+            placements: self.placements.values().cloned().collect(),
+            placement_roles: self.placement_roles.values().cloned().collect(),
+            climbs: self
+                .climbs
+                .values()
+                .filter(|c| !c.origin.is_local())
+                .cloned()
+                .collect(),
+            products: self.products.values().cloned().collect(),
+            products_angles: self
+                .products_angles
+                .iter()
+                .flat_map(|(&product_id, angles)| {
+                    angles
+                        .iter()
+                        .map(move |&angle| ProductAngle { product_id, angle })
+                })
+                .collect(),
+            product_sizes: self.product_sizes.values().cloned().collect(),
+            layouts: self.layouts.values().cloned().collect(),
+            sets: self.sets.values().cloned().collect(),
+            product_sizes_layouts_sets: self.product_sizes_layouts_sets.values().cloned().collect(),
+            leds: self.leds.values().cloned().collect(),
+            climb_stats: self
+                .climb_stats
+                .values()
+                .flat_map(|stats| stats.values().cloned())
+                .collect(),
+            difficulty_grades: self.difficulty_grades.values().cloned().collect(),
+        };
+
+        let mut bytes = MAGIC.to_vec();
+        bytes.extend(SNAPSHOT_VERSION.to_le_bytes());
+        bincode::DefaultOptions::new().serialize_into(&mut bytes, &tables)?;
+
+        Ok(bytes)
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn write_snapshot<P: AsRef<Path>>(&self, path: P) -> Result<(), KilterDataError> {
+        std::fs::write(path, self.to_snapshot()?)?;
+        Ok(())
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn read_snapshot<P: AsRef<Path>>(path: P) -> Result<Self, KilterDataError> {
+        Self::from_snapshot(&std::fs::read(path)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Serialize;
+    use serde_json::{json, Value};
+    use std::collections::BTreeMap;
+
+    use super::*;
+    use crate::kilter_data::ClimbOrigin;
+
+    fn kilter() -> KilterData {
+        let mut kilter = KilterData::default();
+        let climb = |uuid: &str, frames: &str| {
+            serde_json::to_value(Climb {
+                uuid: uuid.to_string(),
+                name: uuid.to_string(),
+                frames: frames.parse().unwrap(),
+                layout_id: 1,
+                is_listed: true,
+                ..Default::default()
+            })
+            .unwrap()
+        };
+
+        kilter
+            .json_update(&json!({
+                "PUT": {
+                    "climbs": [climb("a", "p1r12p2r13"), climb("b", "p2r14")],
+                    "placements": [
+                        { "id": 1, "layout_id": 1, "hole_id": 1, "set_id": 20 },
+                        { "id": 2, "layout_id": 1, "hole_id": 2, "set_id": 21,
+                          "default_placement_role_id": 13 },
+                    ],
+                    "placement_roles": [
+                        { "id": 12, "product_id": 1, "position": 1, "name": "start",
+                          "full_name": "Start", "led_color": "00FF00",
+                          "screen_color": "00DD00" },
+                        { "id": 13, "product_id": 1, "position": 2, "name": "middle",
+                          "full_name": "Middle", "led_color": "00FFFF",
+                          "screen_color": "00FFFF", "min_count_in_climb": 0 },
+                    ],
+                    "sets": [
+                        { "id": 20, "name": "Bolt Ons", "hsm": 1 },
+                        { "id": 21, "name": "Screw Ons", "hsm": 2 },
+                    ],
+                },
+            }))
+            .unwrap();
+        kilter
+    }
+
+    /// Each record by key, for comparing tables whose order isn't fixed.
+    fn table<K: Ord + Clone, V: Serialize>(map: &HashMap<K, V>) -> BTreeMap<K, Value> {
+        map.iter()
+            .map(|(k, v)| (k.clone(), serde_json::to_value(v).unwrap()))
+            .collect()
+    }
+
+    fn climbs(kilter: &KilterData) -> Vec<Value> {
+        kilter
+            .climbs()
+            .values()
+            .map(|c| serde_json::to_value(c).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn round_trips() {
+        let kilter = kilter();
+        let decoded = KilterData::from_snapshot(&kilter.to_snapshot().unwrap()).unwrap();
+
+        assert_eq!(table(&decoded.placements), table(&kilter.placements));
+        assert_eq!(
+            table(&decoded.placement_roles),
+            table(&kilter.placement_roles)
+        );
+        assert_eq!(table(&decoded.sets), table(&kilter.sets));
+        assert_eq!(climbs(&decoded), climbs(&kilter));
+        assert_eq!(decoded.climbs_with_placement(2), [0, 1]);
+        assert_eq!(decoded.climb_set_ids("a"), [20, 21]);
+    }
+
+    #[test]
+    fn leaves_out_local_climbs() {
+        let mut kilter = kilter();
+        kilter.insert_climb(Climb {
+            uuid: "mine".to_string(),
+            origin: ClimbOrigin::Authored,
+            ..Default::default()
+        });
+
+        let decoded = KilterData::from_snapshot(&kilter.to_snapshot().unwrap()).unwrap();
+        assert!(decoded.climb("mine").is_none());
+        assert_eq!(decoded.climbs().len(), 2);
+    }
+
+    #[test]
+    fn rejects_other_data() {
+        let mut bytes = kilter().to_snapshot().unwrap();
+        bytes[0] = b'X';
+        assert!(matches!(
+            KilterData::from_snapshot(&bytes),
+            Err(KilterDataError::NotASnapshot)
+        ));
+
+        assert!(matches!(
+            KilterData::from_snapshot(&MAGIC[..4]),
+            Err(KilterDataError::NotASnapshot)
+        ));
+        assert!(matches!(
+            KilterData::from_snapshot(b"{\"PUT\": {}}"),
+            Err(KilterDataError::NotASnapshot)
+        ));
+    }
+
+    #[test]
+    fn rejects_other_versions() {
+        let mut bytes = kilter().to_snapshot().unwrap();
+        bytes[MAGIC.len()..SNAPSHOT_HEADER_LEN].copy_from_slice(&1u16.to_le_bytes());
+
+        assert!(matches!(
+            KilterData::from_snapshot(&bytes),
+            Err(KilterDataError::SnapshotVersion {
+                found: 1,
+                supported: SNAPSHOT_VERSION,
+            })
+        ));
+    }
+
+    #[test]
+    fn rejects_truncated_or_trailing_bytes() {
+        let bytes = kilter().to_snapshot().unwrap();
+
+        for len in [
+            SNAPSHOT_HEADER_LEN,
+            SNAPSHOT_HEADER_LEN + 1,
+            bytes.len() - 1,
+        ] {
+            assert!(
+                matches!(
+                    KilterData::from_snapshot(&bytes[..len]),
+                    Err(KilterDataError::Binary(_))
+                ),
+                "truncated to {len} bytes"
+            );
+        }
+
+        let mut trailing = bytes.clone();
+        trailing.push(0);
+        assert!(matches!(
+            KilterData::from_snapshot(&trailing),
+            Err(KilterDataError::Binary(_))
+        ));
+    }
+}
@@ -0,0 +1,649 @@
+use std::{hash::Hash, io::Read};
+
+use bevy::platform::collections::{HashMap, HashSet};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::Value;
+
+use super::{
+    error::Change, Climb, ClimbStats, DifficultyGrade, Hole, KilterData, KilterDataError, Layout,
+    Led, LoadReport, Placement, PlacementRole, Product, ProductAngle, ProductSize,
+    ProductSizeLayoutSet, Set,
+};
+
+/// A section of a JSON update.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Op {
+    /// Adds records, or replaces them entirely.
+    Put,
+    /// Changes only the fields given for existing records.
+    Patch,
+    /// Removes records, given as keys or as records with their key fields.
+    Delete,
+}
+impl Op {
+    /// In the order they're applied.
+    const ALL: [Op; 3] = [Op::Put, Op::Patch, Op::Delete];
+
+    fn section(self) -> &'static str {
+        match self {
+            Self::Put => "PUT",
+            Self::Patch => "PATCH",
+            Self::Delete => "DELETE",
+        }
+    }
+}
+
+impl KilterData {
+    /// Applies a JSON update in the format returned by the Kilter Board API.
+    ///
+    /// Records that fail to decode are skipped and reported in the returned
+    /// [`LoadReport`], along with how many records of each table changed.
+    pub fn json_update_reader<R: Read>(
+        &mut self,
+        reader: R,
+    ) -> Result<LoadReport, KilterDataError> {
+        let update: Value = serde_json::from_reader(reader)?;
+
+        self.json_update(&update)
+    }
+
+    /// Applies the `PUT`, `PATCH` and `DELETE` objects of a JSON update, each
+    /// holding records by table name.
+    ///
+    /// Climbs edited locally keep their edits, but take `is_listed` and
+    /// `is_draft` from the update and aren't deleted.
+    pub fn json_update(&mut self, update: &Value) -> Result<LoadReport, KilterDataError> {
+        let sections: Vec<_> = Op::ALL
+            .into_iter()
+            .filter_map(|op| update.get(op.section()).map(|tables| (op, tables)))
+            .collect();
+
+        if sections.is_empty() {
+            return Err(KilterDataError::Schema {
+                table: "PUT",
+                message: "missing PUT, PATCH or DELETE object".to_string(),
+            });
+        }
+
+        let mut report = LoadReport::default();
+        for (op, tables) in sections {
+            report.extend(self.update_tables(op, tables)?);
+        }
+
+        Ok(report)
+    }
+
+    /// Applies an object of records by table name, like the `PUT` object of a
+    /// JSON update or a response from the Kilter Board API's `/sync`.
+    pub fn json_update_tables(&mut self, puts: &Value) -> Result<LoadReport, KilterDataError> {
+        self.update_tables(Op::Put, puts)
+    }
+
+    fn update_tables(&mut self, op: Op, tables: &Value) -> Result<LoadReport, KilterDataError> {
+        let mut report = LoadReport::default();
+
+        self.update_climbs(op, tables, &mut report)?;
+
+        update_table(
+            op,
+            tables,
+            "placements",
+            &mut self.placements,
+            &mut report,
+            |p: &Placement| p.id,
+        )?;
+        update_table(
+            op,
+            tables,
+            "holes",
+            &mut self.holes,
+            &mut report,
+            |h: &Hole| h.id,
+        )?;
+        update_table(
+            op,
+            tables,
+            "placement_roles",
+            &mut self.placement_roles,
+            &mut report,
+            |r: &PlacementRole| r.id,
+        )?;
+        update_table(
+            op,
+            tables,
+            "products",
+            &mut self.products,
+            &mut report,
+            |p: &Product| p.id,
+        )?;
+        self.update_products_angles(op, tables, &mut report)?;
+        update_table(
+            op,
+            tables,
+            "product_sizes",
+            &mut self.product_sizes,
+            &mut report,
+            |s: &ProductSize| s.id,
+        )?;
+        update_table(
+            op,
+            tables,
+            "layouts",
+            &mut self.layouts,
+            &mut report,
+            |l: &Layout| l.id,
+        )?;
+        update_table(
+            op,
+            tables,
+            "sets",
+            &mut self.sets,
+            &mut report,
+            |s: &Set| s.id,
+        )?;
+        update_table(
+            op,
+            tables,
+            "product_sizes_layouts_sets",
+            &mut self.product_sizes_layouts_sets,
+            &mut report,
+            |s: &ProductSizeLayoutSet| s.id,
+        )?;
+        update_table(
+            op,
+            tables,
+            "leds",
+            &mut self.leds,
+            &mut report,
+            |l: &Led| l.id,
+        )?;
+
+        self.update_climb_stats(op, tables, &mut report)?;
+
+        update_table(
+            op,
+            tables,
+            "difficulty_grades",
+            &mut self.difficulty_grades,
+            &mut report,
+            |g: &DifficultyGrade| g.difficulty,
+        )?;
+
+        // Climbs were added before the placements they use.
+        if ["placements", "product_sizes_layouts_sets"]
+            .iter()
+            .any(|table| report.changes.contains_key(table))
+        {
+            self.rebuild_set_indices();
+        }
+        if report.changes.contains_key("leds") {
+            self.rebuild_led_index();
+        }
+        if ["placements", "holes", "product_sizes"]
+            .iter()
+            .any(|table| report.changes.contains_key(table))
+        {
+            self.bump_placements_generation();
+        }
+
+        Ok(report)
+    }
+
+    fn update_climbs(
+        &mut self,
+        op: Op,
+        tables: &Value,
+        report: &mut LoadReport,
+    ) -> Result<(), KilterDataError> {
+        const TABLE: &str = "climbs";
+
+        if op == Op::Delete {
+            let mut uuids = HashSet::new();
+            json_table(tables, TABLE, report, |record, _| {
+                uuids.insert(record_key::<String>(record, "uuid")?);
+                Ok(())
+            })?;
+
+            self.remove_synced_climbs(&uuids, report);
+            return Ok(());
+        }
+
+        json_table(tables, TABLE, report, |record, report| {
+            let existing = self.climbs.get(&record_key::<String>(record, "uuid")?);
+
+            let climb = match (op, existing) {
+                (Op::Patch, Some(existing)) => patch(existing, record)?,
+                (Op::Patch, None) => return Err(missing_record(TABLE)),
+                _ => Climb::deserialize(record)?,
+            };
+
+            let climb = match existing.filter(|c| c.origin.is_local()) {
+                Some(local) => Climb {
+                    is_listed: climb.is_listed,
+                    is_draft: climb.is_draft,
+                    ..local.clone()
+                },
+                None => climb,
+            };
+
+            let change = if existing.is_some() {
+                Change::Updated
+            } else {
+                Change::Added
+            };
+
+            self.insert_climb(climb);
+            report.record_change(TABLE, change);
+            Ok(())
+        })
+    }
+
+    /// Removes the climbs with `uuids` that weren't edited locally, along with
+    /// their stats.
+    fn remove_synced_climbs(&mut self, uuids: &HashSet<String>, report: &mut LoadReport) {
+        let uuids: HashSet<String> = uuids
+            .iter()
+            .filter(|uuid| self.climb(uuid).is_some_and(|c| !c.origin.is_local()))
+            .cloned()
+            .collect();
+
+        for climb in self.remove_climbs(&uuids) {
+            report.record_change("climbs", Change::Removed);
+
+            for _ in self.climb_stats.remove(&climb.uuid).into_iter().flatten() {
+                report.record_change("climb_stats", Change::Removed);
+            }
+        }
+    }
+
+    fn update_products_angles(
+        &mut self,
+        op: Op,
+        tables: &Value,
+        report: &mut LoadReport,
+    ) -> Result<(), KilterDataError> {
+        const TABLE: &str = "products_angles";
+
+        json_table(tables, TABLE, report, |record, report| {
+            let ProductAngle { product_id, angle } = ProductAngle::deserialize(record)?;
+
+            match op {
+                Op::Put => {
+                    let added = self
+                        .products_angles
+                        .entry(product_id)
+                        .or_default()
+                        .insert(angle);
+
+                    report.record_change(
+                        TABLE,
+                        if added {
+                            Change::Added
+                        } else {
+                            Change::Updated
+                        },
+                    );
+                }
+                // Angles have nothing to change but their key.
+                Op::Patch => {
+                    if !self
+                        .products_angles
+                        .get(&product_id)
+                        .is_some_and(|angles| angles.contains(&angle))
+                    {
+                        return Err(missing_record(TABLE));
+                    }
+                }
+                Op::Delete => {
+                    let Some(angles) = self.products_angles.get_mut(&product_id) else {
+                        return Ok(());
+                    };
+
+                    if angles.remove(&angle) {
+                        report.record_change(TABLE, Change::Removed);
+                    }
+
+                    if angles.is_empty() {
+                        self.products_angles.remove(&product_id);
+                    }
+                }
+            }
+
+            Ok(())
+        })
+    }
+
+    fn update_climb_stats(
+        &mut self,
+        op: Op,
+        tables: &Value,
+        report: &mut LoadReport,
+    ) -> Result<(), KilterDataError> {
+        const TABLE: &str = "climb_stats";
+
+        json_table(tables, TABLE, report, |record, report| {
+            let uuid = record_key::<String>(record, "climb_uuid")?;
+            let angle = record.get("angle").map(u32::deserialize).transpose()?;
+
+            match (op, angle) {
+                (Op::Put, _) => {
+                    let stats = ClimbStats::deserialize(record)?;
+                    let old = self
+                        .climb_stats
+                        .entry(stats.climb_uuid.clone())
+                        .or_default()
+                        .insert(stats.angle, stats);
+
+                    report.record_change(
+                        TABLE,
+                        if old.is_some() {
+                            Change::Updated
+                        } else {
+                            Change::Added
+                        },
+                    );
+                }
+                (Op::Patch, Some(angle)) => {
+                    let stats = self
+                        .climb_stats
+                        .get_mut(&uuid)
+                        .and_then(|stats| stats.get_mut(&angle))
+                        .ok_or_else(|| missing_record(TABLE))?;
+
+                    *stats = patch(stats, record)?;
+                    report.record_change(TABLE, Change::Updated);
+                }
+                (Op::Patch, None) => return Err(missing_record(TABLE)),
+                // Without an angle, removes the climb's stats at every angle.
+                (Op::Delete, None) => {
+                    for _ in self.climb_stats.remove(&uuid).into_iter().flatten() {
+                        report.record_change(TABLE, Change::Removed);
+                    }
+                }
+                (Op::Delete, Some(angle)) => {
+                    let Some(stats) = self.climb_stats.get_mut(&uuid) else {
+                        return Ok(());
+                    };
+
+                    if stats.remove(&angle).is_some() {
+                        report.record_change(TABLE, Change::Removed);
+                    }
+
+                    if stats.is_empty() {
+                        self.climb_stats.remove(&uuid);
+                    }
+                }
+            }
+
+            Ok(())
+        })
+    }
+}
+
+/// Applies the `table` records of one section of a JSON update to a table
+/// keyed by `id`, or `difficulty` for grades.
+fn update_table<K, T>(
+    op: Op,
+    tables: &Value,
+    table: &'static str,
+    map: &mut HashMap<K, T>,
+    report: &mut LoadReport,
+    key: impl Fn(&T) -> K,
+) -> Result<(), KilterDataError>
+where
+    K: Eq + Hash + DeserializeOwned,
+    T: Serialize + DeserializeOwned,
+{
+    let key_field = match table {
+        "difficulty_grades" => "difficulty",
+        _ => "id",
+    };
+
+    json_table(tables, table, report, |record, report| {
+        match op {
+            Op::Put => {
+                let item = T::deserialize(record)?;
+                let change = match map.insert(key(&item), item) {
+                    Some(_) => Change::Updated,
+                    None => Change::Added,
+                };
+                report.record_change(table, change);
+            }
+            Op::Patch => {
+                let item = map
+                    .get_mut(&record_key::<K>(record, key_field)?)
+                    .ok_or_else(|| missing_record(table))?;
+
+                *item = patch(item, record)?;
+                report.record_change(table, Change::Updated);
+            }
+            Op::Delete => {
+                if map.remove(&record_key::<K>(record, key_field)?).is_some() {
+                    report.record_change(table, Change::Removed);
+                }
+            }
+        }
+
+        Ok(())
+    })
+}
+
+/// Calls `f` with each record in the `table` array of a section of a JSON
+/// update, if present, skipping and reporting any that fail.
+fn json_table(
+    tables: &Value,
+    table: &'static str,
+    report: &mut LoadReport,
+    mut f: impl FnMut(&Value, &mut LoadReport) -> Result<(), KilterDataError>,
+) -> Result<(), KilterDataError> {
+    let Some(records) = tables.get(table) else {
+        return Ok(());
+    };
+
+    let Some(records) = records.as_array() else {
+        return Err(KilterDataError::Schema {
+            table,
+            message: "expected an array".to_string(),
+        });
+    };
+
+    for (index, record) in records.iter().enumerate() {
+        if let Err(e) = f(record, report) {
+            report.record_error(table, index, e);
+        }
+    }
+
+    Ok(())
+}
+
+/// The key of a record given either as the key itself, or as an object with
+/// a `key_field`.
+fn record_key<K: DeserializeOwned>(record: &Value, key_field: &str) -> Result<K, KilterDataError> {
+    let key = record.get(key_field).unwrap_or(record);
+
+    Ok(K::deserialize(key)?)
+}
+
+/// `existing` with the fields of `fields` replaced.
+fn patch<T: Serialize + DeserializeOwned>(
+    existing: &T,
+    fields: &Value,
+) -> Result<T, KilterDataError> {
+    let mut value = serde_json::to_value(existing)?;
+
+    let (Some(record), Some(fields)) = (value.as_object_mut(), fields.as_object()) else {
+        return Err(KilterDataError::Schema {
+            table: "PATCH",
+            message: "expected an object".to_string(),
+        });
+    };
+
+    for (field, field_value) in fields {
+        record.insert(field.clone(), field_value.clone());
+    }
+
+    Ok(T::deserialize(value)?)
+}
+
+fn missing_record(table: &'static str) -> KilterDataError {
+    KilterDataError::Schema {
+        table,
+        message: "no existing record to patch".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::kilter_data::{ClimbOrigin, TableChanges};
+
+    fn set(id: u32, name: &str) -> Value {
+        json!({ "id": id, "name": name, "hsm": id })
+    }
+
+    fn climb(uuid: &str, name: &str, origin: ClimbOrigin) -> Climb {
+        Climb {
+            uuid: uuid.to_string(),
+            name: name.to_string(),
+            frames: "p1r12".parse().unwrap(),
+            origin,
+            ..Default::default()
+        }
+    }
+
+    fn changes(added: usize, updated: usize, removed: usize) -> TableChanges {
+        TableChanges {
+            added,
+            updated,
+            removed,
+        }
+    }
+
+    #[test]
+    fn counts_changes_by_table() {
+        let mut kilter = KilterData::default();
+        kilter
+            .json_update(&json!({ "PUT": { "sets": [set(1, "Bolt Ons")] } }))
+            .unwrap();
+
+        let report = kilter
+            .json_update(&json!({
+                "PUT": { "sets": [set(1, "Bolt-Ons"), set(2, "Screw Ons"), set(3, "Extra")] },
+                "DELETE": { "sets": [3] },
+            }))
+            .unwrap();
+
+        assert!(report.is_ok());
+        assert_eq!(report.changes.keys().collect::<Vec<_>>(), [&"sets"]);
+        assert_eq!(report.changes["sets"], changes(2, 1, 1));
+        assert_eq!(kilter.sets.len(), 2);
+    }
+
+    #[test]
+    fn patches_only_given_fields() {
+        let mut kilter = KilterData::default();
+        kilter
+            .json_update(&json!({ "PUT": { "sets": [set(1, "Bolt Ons")] } }))
+            .unwrap();
+
+        let report = kilter
+            .json_update(&json!({ "PATCH": { "sets": [{ "id": 1, "name": "Bolt-Ons" }] } }))
+            .unwrap();
+
+        assert_eq!(report.changes["sets"], changes(0, 1, 0));
+        assert_eq!(kilter.sets[&1].name, "Bolt-Ons");
+        assert_eq!(kilter.sets[&1].hsm, 1);
+    }
+
+    #[test]
+    fn reports_patches_of_missing_rows() {
+        let mut kilter = KilterData::default();
+
+        let report = kilter
+            .json_update(&json!({ "PATCH": { "sets": [{ "id": 9, "name": "Missing" }] } }))
+            .unwrap();
+
+        assert!(kilter.sets.is_empty());
+        assert!(report.changes.is_empty());
+        assert!(matches!(
+            report.errors.as_slice(),
+            [KilterDataError::Record {
+                table: "sets",
+                index: 0,
+                ..
+            }]
+        ));
+    }
+
+    #[test]
+    fn deletes_by_key_or_record() {
+        let mut kilter = KilterData::default();
+        let a = serde_json::to_value(climb("a", "A", ClimbOrigin::Database)).unwrap();
+        kilter
+            .json_update(&json!({
+                "PUT": {
+                    "sets": [set(1, "Bolt Ons"), set(2, "Screw Ons"), set(3, "Extra")],
+                    "climbs": [a],
+                    "climb_stats": [{
+                        "climb_uuid": "a", "angle": 40, "display_difficulty": 20.0,
+                        "ascensionist_count": 1, "difficulty_average": 20.0,
+                        "quality_average": 3.0,
+                    }],
+                },
+            }))
+            .unwrap();
+
+        let report = kilter
+            .json_update(&json!({
+                "DELETE": { "sets": [1, { "id": 2 }, 9], "climbs": [{ "uuid": "a" }] },
+            }))
+            .unwrap();
+
+        assert!(report.is_ok());
+        assert_eq!(kilter.sets.keys().collect::<Vec<_>>(), [&3]);
+        assert!(kilter.climb("a").is_none());
+        assert!(kilter.climb_stats.is_empty());
+        assert_eq!(report.changes["sets"], changes(0, 0, 2));
+        assert_eq!(report.changes["climbs"], changes(0, 0, 1));
+        assert_eq!(report.changes["climb_stats"], changes(0, 0, 1));
+    }
+
+    #[test]
+    fn keeps_local_climbs_but_takes_visibility() {
+        let mut kilter = KilterData::default();
+        kilter.insert_climb(climb("authored", "Mine", ClimbOrigin::Authored));
+        kilter.insert_climb(climb("edited", "Changed", ClimbOrigin::Edited));
+
+        let upstream = |uuid: &str| {
+            serde_json::to_value(Climb {
+                is_listed: true,
+                is_draft: true,
+                frames: "p2r13".parse().unwrap(),
+                ..climb(uuid, "Upstream", ClimbOrigin::Database)
+            })
+            .unwrap()
+        };
+        let report = kilter
+            .json_update(&json!({
+                "PUT": { "climbs": [upstream("authored")] },
+                "PATCH": { "climbs": [{ "uuid": "edited", "name": "Patched", "is_listed": true }] },
+                "DELETE": { "climbs": ["authored", "edited"] },
+            }))
+            .unwrap();
+
+        assert_eq!(report.changes["climbs"], changes(0, 2, 0));
+
+        let authored = kilter.climb("authored").unwrap();
+        assert_eq!(authored.name, "Mine");
+        assert_eq!(authored.frames.to_string(), "p1r12");
+        assert_eq!(authored.origin, ClimbOrigin::Authored);
+        assert!(authored.is_listed && authored.is_draft);
+
+        let edited = kilter.climb("edited").unwrap();
+        assert_eq!(edited.name, "Changed");
+        assert_eq!(edited.origin, ClimbOrigin::Edited);
+        assert!(edited.is_listed && !edited.is_draft);
+    }
+}
@@ -0,0 +1,156 @@
+use std::fmt::{self, Display};
+
+use super::{Climb, KilterData, PlacementRole};
+
+/// Something about a climb that breaks the board's rules.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ClimbIssue {
+    /// The climb has no holds.
+    Empty,
+    TooFew {
+        role: String,
+        count: u32,
+        min: u32,
+    },
+    TooMany {
+        role: String,
+        count: u32,
+        max: u32,
+    },
+    /// A placement appears more than once in a frame.
+    DuplicatePlacement(u32),
+    UnknownPlacement(u32),
+    /// A placement isn't on the climb's layout, or doesn't fit on the product
+    /// size.
+    PlacementNotOnBoard(u32),
+    /// A role that doesn't exist or belongs to another product.
+    InvalidRole {
+        placement_id: u32,
+        role_id: u32,
+    },
+}
+impl Display for ClimbIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Empty => write!(f, "No holds"),
+            Self::TooFew { role, count, min } => {
+                write!(f, "{count} {role} hold(s), needs at least {min}")
+            }
+            Self::TooMany { role, count, max } => {
+                write!(f, "{count} {role} hold(s), allows at most {max}")
+            }
+            Self::DuplicatePlacement(id) => write!(f, "Placement {id} is used twice"),
+            Self::UnknownPlacement(id) => write!(f, "Unknown placement {id}"),
+            Self::PlacementNotOnBoard(id) => write!(f, "Placement {id} isn't on this board"),
+            Self::InvalidRole {
+                placement_id,
+                role_id,
+            } => write!(f, "Placement {placement_id} has invalid role {role_id}"),
+        }
+    }
+}
+
+impl PlacementRole {
+    /// The fewest and most holds with this role a climb may have.
+    ///
+    /// Falls back to the usual limit of one or two start and finish holds
+    /// when the database doesn't specify any.
+    pub fn count_limits(&self) -> (Option<u32>, Option<u32>) {
+        match (self.min_count_in_climb, self.max_count_in_climb) {
+            (None, None) if matches!(self.name.as_str(), "start" | "finish") => (Some(1), Some(2)),
+            limits => limits,
+        }
+    }
+}
+
+impl KilterData {
+    /// Checks `climb` against the rules for its layout, and `product_size_id`
+    /// if given.
+    pub fn validate_climb(&self, climb: &Climb, product_size_id: Option<u32>) -> Vec<ClimbIssue> {
+        let mut issues = vec![];
+
+        if climb.frames.iter().all(|f| f.is_empty()) {
+            issues.push(ClimbIssue::Empty);
+            return issues;
+        }
+
+        issues.extend(
+            climb
+                .frames
+                .duplicate_placements()
+                .into_iter()
+                .map(ClimbIssue::DuplicatePlacement),
+        );
+
+        let product_id = self.layout_product_id(climb.layout_id);
+
+        for placement_id in climb.frames.placement_ids() {
+            if !self.placements.contains_key(&placement_id) {
+                issues.push(ClimbIssue::UnknownPlacement(placement_id));
+            } else if !self.placement_on(placement_id, climb.layout_id, product_size_id) {
+                issues.push(ClimbIssue::PlacementNotOnBoard(placement_id));
+            }
+        }
+
+        for placement in climb.frames.iter().flat_map(|f| f.iter()) {
+            let valid = self
+                .placement_roles
+                .get(&placement.role_id)
+                .is_some_and(|role| product_id.is_none_or(|p| role.product_id == p));
+            if !valid {
+                issues.push(ClimbIssue::InvalidRole {
+                    placement_id: placement.placement_id,
+                    role_id: placement.role_id,
+                });
+            }
+        }
+
+        let Some(product_id) = product_id else {
+            return issues;
+        };
+
+        for role in self.sorted_placement_roles(product_id) {
+            let count = self.role_count(climb, role.id);
+            let (min, max) = role.count_limits();
+
+            if let Some(min) = min.filter(|min| count < *min) {
+                issues.push(ClimbIssue::TooFew {
+                    role: role.name.clone(),
+                    count,
+                    min,
+                });
+            }
+
+            if let Some(max) = max.filter(|max| count > *max) {
+                issues.push(ClimbIssue::TooMany {
+                    role: role.name.clone(),
+                    count,
+                    max,
+                });
+            }
+        }
+
+        issues
+    }
+
+    /// How many different placements have `role_id` in any frame of `climb`.
+    pub fn role_count(&self, climb: &Climb, role_id: u32) -> u32 {
+        climb
+            .frames
+            .placement_ids()
+            .into_iter()
+            .filter(|id| climb.frames.contains(*id, Some(role_id)))
+            .count() as u32
+    }
+
+    /// Whether `climb` already has as many holds with `role_id` as it may.
+    pub fn role_is_full(&self, climb: &Climb, role_id: u32) -> bool {
+        let Some(role) = self.placement_roles.get(&role_id) else {
+            return false;
+        };
+
+        role.count_limits()
+            .1
+            .is_some_and(|max| self.role_count(climb, role_id) >= max)
+    }
+}
@@ -0,0 +1,279 @@
+//! Lighting climbs on a real board over Bluetooth, using the protocol spoken by
+//! Aurora boards like the Kilter Board.
+
+use std::fmt::{self, Display};
+
+pub use protocol::{climb_leds, decode, encode, ApiLevel, LedColor, LitLed, PacketKind};
+pub use simulated::SimulatedBoard;
+
+mod protocol;
+mod simulated;
+
+/// The most bytes a board accepts in a single Bluetooth write.
+pub const WRITE_SIZE: usize = 20;
+
+/// Somewhere to send encoded messages, e.g. a board's Bluetooth UART
+/// characteristic.
+pub trait LedTransport {
+    type Error;
+
+    /// Writes up to [`WRITE_SIZE`] bytes.
+    fn write(&mut self, bytes: &[u8]) -> Result<(), Self::Error>;
+}
+
+/// Sends `messages` from [`encode`] to `transport`, split into writes the
+/// board accepts.
+pub fn send<T: LedTransport>(transport: &mut T, messages: &[Vec<u8>]) -> Result<(), T::Error> {
+    let bytes: Vec<u8> = messages.concat();
+
+    for chunk in bytes.chunks(WRITE_SIZE) {
+        transport.write(chunk)?;
+    }
+
+    Ok(())
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LedError {
+    UnknownPlacement(u32),
+    /// The placement's hole has no LED on the product size.
+    NoLed {
+        placement_id: u32,
+        product_size_id: u32,
+    },
+    /// The position doesn't fit in the API level's encoding.
+    PositionOutOfRange {
+        position: u32,
+        api_level: ApiLevel,
+    },
+    InvalidRole(u32),
+    InvalidColor(String),
+    /// A received message isn't framed as expected.
+    Framing(&'static str),
+    Checksum {
+        expected: u8,
+        actual: u8,
+    },
+    UnknownCommand(u8),
+}
+
+impl Display for LedError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnknownPlacement(id) => write!(f, "unknown placement {id}"),
+            Self::NoLed {
+                placement_id,
+                product_size_id,
+            } => write!(
+                f,
+                "placement {placement_id} has no LED on product size {product_size_id}"
+            ),
+            Self::PositionOutOfRange {
+                position,
+                api_level,
+            } => write!(f, "LED position {position} is too large for {api_level:?}"),
+            Self::InvalidRole(id) => write!(f, "invalid role {id}"),
+            Self::InvalidColor(color) => write!(f, "invalid LED color {color}"),
+            Self::Framing(message) => write!(f, "bad framing: {message}"),
+            Self::Checksum { expected, actual } => {
+                write!(
+                    f,
+                    "bad checksum: expected {expected:#04x}, got {actual:#04x}"
+                )
+            }
+            Self::UnknownCommand(command) => write!(f, "unknown command {command:#04x}"),
+        }
+    }
+}
+
+impl std::error::Error for LedError {}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::kilter_data::{Climb, KilterData};
+
+    /// Records each write, and passes it on to a simulated board.
+    struct Recorder {
+        writes: Vec<Vec<u8>>,
+        board: SimulatedBoard,
+    }
+    impl LedTransport for Recorder {
+        type Error = LedError;
+
+        fn write(&mut self, bytes: &[u8]) -> Result<(), LedError> {
+            self.writes.push(bytes.to_vec());
+            self.board.write(bytes)
+        }
+    }
+
+    fn leds(count: u32) -> Vec<LitLed> {
+        (0..count)
+            .map(|i| LitLed {
+                position: i,
+                color: LedColor {
+                    r: (i * 7) as u8,
+                    g: (i * 13) as u8,
+                    b: (i * 29) as u8,
+                },
+            })
+            .collect()
+    }
+
+    fn quantized(leds: &[LitLed], api_level: ApiLevel) -> Vec<LitLed> {
+        leds.iter()
+            .map(|l| LitLed {
+                color: l.color.quantized(api_level),
+                ..*l
+            })
+            .collect()
+    }
+
+    fn commands(messages: &[Vec<u8>]) -> String {
+        messages.iter().map(|m| m[4] as char).collect()
+    }
+
+    fn role(id: u32, led_color: &str) -> serde_json::Value {
+        json!({
+            "id": id, "product_id": 1, "position": id, "name": "", "full_name": "",
+            "led_color": led_color, "screen_color": led_color,
+        })
+    }
+
+    #[test]
+    fn lights_a_climb_on_a_simulated_board() {
+        let mut kilter = KilterData::default();
+        let climb = Climb {
+            uuid: "a".to_string(),
+            frames: "p1r12p2r13p3r14".parse().unwrap(),
+            ..Default::default()
+        };
+        let placements = [1, 2, 3]
+            .map(|id| json!({ "id": id, "layout_id": 1, "hole_id": id + 100, "set_id": 1 }));
+        let leds = [1, 2, 3].map(|id| {
+            json!({ "id": id, "product_size_id": 10, "hole_id": id + 100, "position": id * 300 })
+        });
+        kilter
+            .json_update(&json!({
+                "PUT": {
+                    "climbs": [serde_json::to_value(climb).unwrap()],
+                    "placements": placements,
+                    "leds": leds,
+                    "placement_roles": [role(12, "00FF00"), role(13, "00FFFF"), role(14, "FF00FF")],
+                },
+            }))
+            .unwrap();
+
+        let leds = climb_leds(&kilter, kilter.climb("a").unwrap(), 0, 10).unwrap();
+        let expected = [
+            (300, LedColor { r: 0, g: 255, b: 0 }),
+            (
+                600,
+                LedColor {
+                    r: 0,
+                    g: 255,
+                    b: 255,
+                },
+            ),
+            (
+                900,
+                LedColor {
+                    r: 255,
+                    g: 0,
+                    b: 255,
+                },
+            ),
+        ]
+        .map(|(position, color)| LitLed { position, color });
+        assert_eq!(leds, expected);
+
+        for api_level in [ApiLevel::V2, ApiLevel::V3] {
+            let mut board = SimulatedBoard::new(api_level);
+            send(&mut board, &encode(&leds, api_level).unwrap()).unwrap();
+            assert_eq!(board.lit(), quantized(&expected, api_level));
+        }
+
+        assert_eq!(
+            climb_leds(&kilter, kilter.climb("a").unwrap(), 0, 11),
+            Err(LedError::NoLed {
+                placement_id: 1,
+                product_size_id: 11,
+            })
+        );
+    }
+
+    #[test]
+    fn uses_each_api_levels_commands() {
+        // Up to 127 LEDs fit in a V2 message, and 84 in a V3 one.
+        for (api_level, count, expected) in [
+            (ApiLevel::V2, 0, "P"),
+            (ApiLevel::V2, 127, "P"),
+            (ApiLevel::V2, 128, "RS"),
+            (ApiLevel::V2, 300, "RQS"),
+            (ApiLevel::V3, 0, "T"),
+            (ApiLevel::V3, 84, "T"),
+            (ApiLevel::V3, 85, "NO"),
+            (ApiLevel::V3, 200, "NMO"),
+        ] {
+            let leds = leds(count);
+            let messages = encode(&leds, api_level).unwrap();
+            assert_eq!(commands(&messages), expected, "{api_level:?} {count}");
+
+            let mut board = SimulatedBoard::new(api_level);
+            send(&mut board, &messages).unwrap();
+            assert_eq!(board.lit(), quantized(&leds, api_level));
+        }
+    }
+
+    #[test]
+    fn frames_messages_with_a_checksum() {
+        let led = LitLed {
+            position: 0x102,
+            color: LedColor { r: 255, g: 0, b: 0 },
+        };
+
+        let message = &encode(&[led], ApiLevel::V3).unwrap()[0];
+        // T, position 0x0102 little-endian, then red in 3-3-2 bits.
+        let data = [b'T', 0x02, 0x01, 0xe0];
+        let checksum = !data.iter().fold(0u8, |sum, b| sum.wrapping_add(*b));
+        assert_eq!(message[..4], [0x01, 4, checksum, 0x02]);
+        assert_eq!(message[4..8], data);
+        assert_eq!(message[8], 0x03);
+
+        let mut corrupt = message.clone();
+        corrupt[6] ^= 0x01;
+        assert!(matches!(
+            SimulatedBoard::new(ApiLevel::V3).write(&corrupt),
+            Err(LedError::Checksum { .. })
+        ));
+
+        assert_eq!(
+            encode(&leds(1025), ApiLevel::V2),
+            Err(LedError::PositionOutOfRange {
+                position: 1024,
+                api_level: ApiLevel::V2,
+            })
+        );
+    }
+
+    #[test]
+    fn splits_messages_into_writes_the_board_accepts() {
+        let leds = leds(200);
+        let messages = encode(&leds, ApiLevel::V3).unwrap();
+
+        let mut recorder = Recorder {
+            writes: vec![],
+            board: SimulatedBoard::new(ApiLevel::V3),
+        };
+        send(&mut recorder, &messages).unwrap();
+
+        assert!(recorder.writes.iter().all(|w| w.len() <= WRITE_SIZE));
+        assert!(recorder.writes[..recorder.writes.len() - 1]
+            .iter()
+            .all(|w| w.len() == WRITE_SIZE));
+        assert_eq!(recorder.writes.concat(), messages.concat());
+        assert_eq!(recorder.board.lit(), quantized(&leds, ApiLevel::V3));
+    }
+}
@@ -0,0 +1,303 @@
+use crate::kilter_data::{Climb, KilterData};
+
+use super::LedError;
+
+/// Starts a message.
+const SOH: u8 = 0x01;
+/// Starts a message's data.
+const STX: u8 = 0x02;
+/// Ends a message.
+const ETX: u8 = 0x03;
+
+/// The most bytes of data, including the command, in one message.
+const MAX_DATA_LEN: usize = 255;
+/// The bytes framing a message's data.
+const FRAMING_LEN: usize = 5;
+
+/// The version of the protocol a board's firmware speaks.
+///
+/// Older boards use [`ApiLevel::V2`], which packs colors more coarsely and
+/// only addresses 1024 LEDs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ApiLevel {
+    V2,
+    V3,
+}
+impl ApiLevel {
+    fn bytes_per_led(self) -> usize {
+        match self {
+            Self::V2 => 2,
+            Self::V3 => 3,
+        }
+    }
+
+    fn max_position(self) -> u32 {
+        match self {
+            Self::V2 => 0x3ff,
+            Self::V3 => 0xffff,
+        }
+    }
+
+    fn command(self, kind: PacketKind) -> u8 {
+        match (self, kind) {
+            (Self::V2, PacketKind::Only) => b'P',
+            (Self::V2, PacketKind::Middle) => b'Q',
+            (Self::V2, PacketKind::First) => b'R',
+            (Self::V2, PacketKind::Last) => b'S',
+            (Self::V3, PacketKind::Middle) => b'M',
+            (Self::V3, PacketKind::First) => b'N',
+            (Self::V3, PacketKind::Last) => b'O',
+            (Self::V3, PacketKind::Only) => b'T',
+        }
+    }
+
+    fn packet_kind(self, command: u8) -> Option<PacketKind> {
+        [
+            PacketKind::Only,
+            PacketKind::First,
+            PacketKind::Middle,
+            PacketKind::Last,
+        ]
+        .into_iter()
+        .find(|kind| self.command(*kind) == command)
+    }
+
+    fn encode_led(self, led: &LitLed, out: &mut Vec<u8>) {
+        let LedColor { r, g, b } = led.color;
+        let position = led.position;
+
+        match self {
+            Self::V2 => {
+                out.push(position as u8);
+                out.push(
+                    ((position >> 8) & 0x03) as u8 | (r / 64) << 6 | (g / 64) << 4 | (b / 64) << 2,
+                );
+            }
+            Self::V3 => {
+                out.push(position as u8);
+                out.push((position >> 8) as u8);
+                out.push((r / 32) << 5 | (g / 32) << 2 | (b / 64));
+            }
+        }
+    }
+
+    fn decode_led(self, bytes: &[u8]) -> LitLed {
+        // Spreads a quantized channel back over 0-255.
+        fn expand(value: u8, max: u8) -> u8 {
+            (value as u32 * 255 / max as u32) as u8
+        }
+
+        match self {
+            Self::V2 => LitLed {
+                position: bytes[0] as u32 | ((bytes[1] & 0x03) as u32) << 8,
+                color: LedColor {
+                    r: expand(bytes[1] >> 6 & 0x03, 3),
+                    g: expand(bytes[1] >> 4 & 0x03, 3),
+                    b: expand(bytes[1] >> 2 & 0x03, 3),
+                },
+            },
+            Self::V3 => LitLed {
+                position: bytes[0] as u32 | (bytes[1] as u32) << 8,
+                color: LedColor {
+                    r: expand(bytes[2] >> 5 & 0x07, 7),
+                    g: expand(bytes[2] >> 2 & 0x07, 7),
+                    b: expand(bytes[2] & 0x03, 3),
+                },
+            },
+        }
+    }
+}
+
+/// Where a message falls in a sequence lighting the board. Boards clear their
+/// LEDs on the first message, and light them all on the last.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PacketKind {
+    Only,
+    First,
+    Middle,
+    Last,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct LedColor {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+}
+impl LedColor {
+    /// Parses a color like `00FF00` or `#00FF00`, as used by
+    /// [`PlacementRole::led_color`](crate::kilter_data::PlacementRole).
+    pub fn from_hex(hex: &str) -> Option<Self> {
+        let hex = hex.strip_prefix('#').unwrap_or(hex);
+        if hex.len() != 6 || !hex.is_ascii() {
+            return None;
+        }
+
+        let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+
+        Some(Self {
+            r: channel(0)?,
+            g: channel(2)?,
+            b: channel(4)?,
+        })
+    }
+
+    /// The color as the board shows it after encoding at `api_level`.
+    pub fn quantized(self, api_level: ApiLevel) -> Self {
+        let mut bytes = vec![];
+        api_level.encode_led(
+            &LitLed {
+                position: 0,
+                color: self,
+            },
+            &mut bytes,
+        );
+        api_level.decode_led(&bytes).color
+    }
+}
+
+/// An LED to light, by its position in the board's strip.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LitLed {
+    pub position: u32,
+    pub color: LedColor,
+}
+
+/// The LEDs to light for `frame` of `climb` on a board of `product_size_id`,
+/// in the colors of each hold's role.
+pub fn climb_leds(
+    kilter: &KilterData,
+    climb: &Climb,
+    frame: usize,
+    product_size_id: u32,
+) -> Result<Vec<LitLed>, LedError> {
+    let Some(frame) = climb.frames.get(frame) else {
+        return Ok(vec![]);
+    };
+
+    frame
+        .iter()
+        .map(|p| {
+            if !kilter.placements.contains_key(&p.placement_id) {
+                return Err(LedError::UnknownPlacement(p.placement_id));
+            }
+
+            let position =
+                kilter
+                    .led_position(product_size_id, p.placement_id)
+                    .ok_or(LedError::NoLed {
+                        placement_id: p.placement_id,
+                        product_size_id,
+                    })?;
+
+            let role = kilter
+                .placement_roles
+                .get(&p.role_id)
+                .ok_or(LedError::InvalidRole(p.role_id))?;
+
+            let color = LedColor::from_hex(&role.led_color)
+                .ok_or_else(|| LedError::InvalidColor(role.led_color.clone()))?;
+
+            Ok(LitLed { position, color })
+        })
+        .collect()
+}
+
+/// Encodes `leds` into framed messages that light exactly those LEDs, split
+/// so that no message is too long.
+pub fn encode(leds: &[LitLed], api_level: ApiLevel) -> Result<Vec<Vec<u8>>, LedError> {
+    if let Some(led) = leds.iter().find(|l| l.position > api_level.max_position()) {
+        return Err(LedError::PositionOutOfRange {
+            position: led.position,
+            api_level,
+        });
+    }
+
+    let per_message = (MAX_DATA_LEN - 1) / api_level.bytes_per_led();
+
+    // An empty climb still needs a message to clear the board.
+    let chunks: Vec<&[LitLed]> = if leds.is_empty() {
+        vec![&[]]
+    } else {
+        leds.chunks(per_message).collect()
+    };
+
+    let last = chunks.len() - 1;
+
+    Ok(chunks
+        .into_iter()
+        .enumerate()
+        .map(|(i, chunk)| {
+            let kind = match i {
+                _ if last == 0 => PacketKind::Only,
+                0 => PacketKind::First,
+                i if i == last => PacketKind::Last,
+                _ => PacketKind::Middle,
+            };
+
+            let mut data = vec![api_level.command(kind)];
+            for led in chunk {
+                api_level.encode_led(led, &mut data);
+            }
+
+            frame(&data)
+        })
+        .collect())
+}
+
+/// Wraps `data` in a message's framing.
+fn frame(data: &[u8]) -> Vec<u8> {
+    let mut message = Vec::with_capacity(data.len() + FRAMING_LEN);
+    message.extend([SOH, data.len() as u8, checksum(data), STX]);
+    message.extend(data);
+    message.push(ETX);
+    message
+}
+
+fn checksum(data: &[u8]) -> u8 {
+    data.iter().fold(0u8, |sum, b| sum.wrapping_add(*b)) ^ 0xff
+}
+
+/// The length of the message at the start of `bytes`, if enough of it has
+/// arrived to tell.
+pub(super) fn message_len(bytes: &[u8]) -> Option<usize> {
+    bytes.get(1).map(|len| *len as usize + FRAMING_LEN)
+}
+
+/// Decodes one framed message from [`encode`].
+pub fn decode(message: &[u8], api_level: ApiLevel) -> Result<(PacketKind, Vec<LitLed>), LedError> {
+    let [SOH, len, expected, STX, data @ .., ETX] = message else {
+        return Err(LedError::Framing("missing start or end bytes"));
+    };
+
+    if data.len() != *len as usize {
+        return Err(LedError::Framing("length doesn't match"));
+    }
+
+    let actual = checksum(data);
+    if actual != *expected {
+        return Err(LedError::Checksum {
+            expected: *expected,
+            actual,
+        });
+    }
+
+    let Some((command, leds)) = data.split_first() else {
+        return Err(LedError::Framing("missing command"));
+    };
+
+    let kind = api_level
+        .packet_kind(*command)
+        .ok_or(LedError::UnknownCommand(*command))?;
+
+    if leds.len() % api_level.bytes_per_led() != 0 {
+        return Err(LedError::Framing("partial LED"));
+    }
+
+    Ok((
+        kind,
+        leds.chunks(api_level.bytes_per_led())
+            .map(|bytes| api_level.decode_led(bytes))
+            .collect(),
+    ))
+}
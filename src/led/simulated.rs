@@ -0,0 +1,75 @@
+use super::{
+    protocol::{decode, message_len},
+    ApiLevel, LedError, LedTransport, LitLed, PacketKind,
+};
+
+/// A board that decodes what's sent to it instead of lighting anything, for
+/// trying out the protocol without hardware.
+#[derive(Debug)]
+pub struct SimulatedBoard {
+    api_level: ApiLevel,
+    /// Bytes of a message that hasn't fully arrived.
+    buffer: Vec<u8>,
+    /// LEDs from a sequence of messages that hasn't finished.
+    pending: Vec<LitLed>,
+    lit: Vec<LitLed>,
+}
+impl SimulatedBoard {
+    pub fn new(api_level: ApiLevel) -> Self {
+        Self {
+            api_level,
+            buffer: vec![],
+            pending: vec![],
+            lit: vec![],
+        }
+    }
+
+    /// The LEDs lit by the last complete sequence of messages.
+    pub fn lit(&self) -> &[LitLed] {
+        &self.lit
+    }
+
+    fn receive(&mut self, message: &[u8]) -> Result<(), LedError> {
+        let (kind, leds) = decode(message, self.api_level)?;
+
+        match kind {
+            PacketKind::Only => {
+                self.lit = leds;
+            }
+            PacketKind::First => {
+                self.pending = leds;
+            }
+            PacketKind::Middle => {
+                self.pending.extend(leds);
+            }
+            PacketKind::Last => {
+                self.pending.extend(leds);
+                self.lit = std::mem::take(&mut self.pending);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl LedTransport for SimulatedBoard {
+    type Error = LedError;
+
+    fn write(&mut self, bytes: &[u8]) -> Result<(), LedError> {
+        self.buffer.extend(bytes);
+
+        while let Some(len) = message_len(&self.buffer).filter(|len| self.buffer.len() >= *len) {
+            let message: Vec<u8> = self.buffer.drain(..len).collect();
+
+            if let Err(e) = self.receive(&message) {
+                // Start over at the next message rather than misreading the
+                // rest of this one.
+                self.buffer.clear();
+                self.pending.clear();
+                return Err(e);
+            }
+        }
+
+        Ok(())
+    }
+}
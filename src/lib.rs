@@ -2,24 +2,37 @@ use authoring::AuthoringPlugin;
 use bevy::prelude::*;
 use bevy_simple_text_input::TextInputPlugin;
 use clipboard::ClipboardPlugin;
+use data_loading::DataLoadingPlugin;
 use debug::DebugPlugin;
 use gen_api::GenApiPlugin;
+use history::HistoryPlugin;
 use human::HumanPlugin;
 use kilter_board::KilterBoardPlugin;
 use pan_cam::PanCamPlugin;
+use placement_index::PlacementIndexPlugin;
 use placement_indicator::PlacementIndicatorPlugin;
+#[cfg(not(any(target_arch = "wasm32", target_os = "android", target_os = "ios")))]
+use sync_api::SyncApiPlugin;
 use ui::UiPlugin;
+use user_store::UserStorePlugin;
 
 mod authoring;
 mod clipboard;
+pub mod data_loading;
 mod debug;
 mod gen_api;
+mod history;
 mod human;
 mod kilter_board;
 pub mod kilter_data;
+pub mod led;
 mod pan_cam;
+mod placement_index;
 mod placement_indicator;
+#[cfg(not(any(target_arch = "wasm32", target_os = "android", target_os = "ios")))]
+mod sync_api;
 mod ui;
+mod user_store;
 
 pub struct AppPlugin;
 
@@ -30,17 +43,24 @@ impl Plugin for AppPlugin {
 
         // Our plugins
         app.add_plugins((
+            DataLoadingPlugin,
             GenApiPlugin,
+            HistoryPlugin,
             HumanPlugin,
             AuthoringPlugin,
             ClipboardPlugin,
+            PlacementIndexPlugin,
             PlacementIndicatorPlugin,
             PanCamPlugin,
             DebugPlugin,
             KilterBoardPlugin,
             UiPlugin,
+            UserStorePlugin,
         ));
 
+        #[cfg(not(any(target_arch = "wasm32", target_os = "android", target_os = "ios")))]
+        app.add_plugins(SyncApiPlugin);
+
         // Third-party Plugins
         app.add_plugins(TextInputPlugin);
     }
@@ -1,29 +1,98 @@
 use bevy::prelude::*;
 
-use kilter_brain::{kilter_data::KilterData, AppPlugin};
+#[cfg(not(target_arch = "wasm32"))]
+use bevy::asset::io::AssetSourceBuilder;
+#[cfg(not(target_arch = "wasm32"))]
+use kilter_brain::kilter_data::{
+    check_snapshot, KilterDataError, DATABASE_PATH, SNAPSHOT_HEADER_LEN, SNAPSHOT_PATH,
+    UPDATES_PATH,
+};
+use kilter_brain::{data_loading::KilterDataSource, AppPlugin};
+#[cfg(not(target_arch = "wasm32"))]
+use std::{
+    fs::{self, File},
+    io::Read,
+    path::{Path, PathBuf},
+};
+
+/// The asset source for the database, JSON updates and snapshot on native.
+#[cfg(not(target_arch = "wasm32"))]
+const DATA_SOURCE: &str = "kilter_brain_data";
 
 fn main() {
-    // Just embed some minimal json on the web for now. In the future we will want to
-    // be able to load this data from an API endpoint or perhaps just through Bevy's
-    // asset server.
+    let mut app = App::new();
+
+    // On the web, load a small set of climbs through the asset server.
     #[cfg(target_arch = "wasm32")]
-    let kd = {
-        let mut kd = KilterData::default();
-        kd.json_update_reader(std::io::Cursor::new(include_str!("../minimal.json")));
-        kd
-    };
+    app.insert_resource(KilterDataSource("minimal.json".to_string()));
+
     #[cfg(not(target_arch = "wasm32"))]
-    let kd = {
-        let mut kd = KilterData::from_sqlite("../kilter_brain_data/db.sqlite3").unwrap();
-        if let Err(e) = kd.json_update_files("../kilter_brain_data/api_json") {
-            eprintln!("Failed to load JSON updates. {:?}", e);
-        };
-        kd
+    {
+        // Asset sources have to be registered before the asset plugin.
+        app.register_asset_source(
+            DATA_SOURCE,
+            AssetSourceBuilder::platform_default("../kilter_brain_data", None),
+        );
+        app.insert_resource(KilterDataSource(native_source()));
+    }
+
+    app.add_plugins(DefaultPlugins).add_plugins(AppPlugin).run();
+}
+
+/// The snapshot if it's up to date, or else the database, which writes a new
+/// snapshot for next time once it's loaded.
+#[cfg(not(target_arch = "wasm32"))]
+fn native_source() -> String {
+    let file_name = |path: &str| {
+        let name = Path::new(path).file_name().unwrap_or_default();
+        format!("{}://{}", DATA_SOURCE, name.to_string_lossy())
+    };
+
+    if snapshot_is_fresh() {
+        file_name(SNAPSHOT_PATH)
+    } else if Path::new(DATABASE_PATH).exists() {
+        file_name(DATABASE_PATH)
+    } else {
+        eprintln!(
+            "No database at {}. Using minimal.json instead.",
+            DATABASE_PATH
+        );
+        "minimal.json".to_string()
+    }
+}
+
+/// Whether the snapshot was written by this version of the app, after the
+/// database and every JSON update last changed.
+#[cfg(not(target_arch = "wasm32"))]
+fn snapshot_is_fresh() -> bool {
+    let modified = |path: &Path| fs::metadata(path).and_then(|m| m.modified()).ok();
+
+    let Some(snapshot) = modified(Path::new(SNAPSHOT_PATH)) else {
+        return false;
     };
 
-    App::new()
-        .insert_resource(kd)
-        .add_plugins(DefaultPlugins)
-        .add_plugins(AppPlugin)
-        .run();
+    if let Err(e) = read_snapshot_header().and_then(|header| check_snapshot(&header).map(drop)) {
+        eprintln!("Rebuilding the snapshot. {}", e);
+        return false;
+    }
+
+    let updates = fs::read_dir(UPDATES_PATH)
+        .into_iter()
+        .flatten()
+        .filter_map(Result::ok)
+        .map(|entry| entry.path());
+
+    std::iter::once(PathBuf::from(DATABASE_PATH))
+        .chain(updates)
+        .all(|path| modified(&path).is_some_and(|m| m <= snapshot))
+}
+
+/// The magic and version at the start of the snapshot.
+#[cfg(not(target_arch = "wasm32"))]
+fn read_snapshot_header() -> Result<Vec<u8>, KilterDataError> {
+    let mut header = vec![];
+    File::open(SNAPSHOT_PATH)?
+        .take(SNAPSHOT_HEADER_LEN as u64)
+        .read_to_end(&mut header)?;
+    Ok(header)
 }
@@ -3,42 +3,71 @@ use bevy::{
         gestures::PinchGesture,
         mouse::{MouseScrollUnit, MouseWheel},
     },
-    picking::events::{Drag, Pointer},
+    picking::{
+        events::{Drag, Pointer},
+        hover::HoverMap,
+    },
     prelude::*,
 };
 
+use crate::{
+    authoring::{DraggedHold, HoldDragSystems},
+    kilter_board::{self, Board, BoardPivot},
+};
+
 #[derive(Default)]
 pub struct PanCamPlugin;
 
 impl Plugin for PanCamPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Update, (camera_movement, camera_zoom))
-            .add_systems(Startup, setup);
+        app.add_systems(
+            Update,
+            (
+                camera_movement.after(HoldDragSystems),
+                camera_zoom,
+                follow_board
+                    .after(camera_movement)
+                    .after(camera_zoom)
+                    .after(kilter_board::tilt_board),
+            ),
+        )
+        .add_systems(Startup, setup);
     }
 }
 
 fn setup(mut commands: Commands) {
     let pos = Vec3::new(-2.0, 1.0, 6.0);
+    let view = Transform::from_translation(pos).looking_at(Vec3::ZERO, Vec3::Y);
 
     commands.spawn((
         Camera3d::default(),
-        Transform::from_translation(pos).looking_at(Vec3::ZERO, Vec3::Y),
+        view,
         PanCam {
             bounds_min: pos.truncate(),
             bounds_max: pos.truncate(),
+            view,
             ..default()
         },
     ));
 }
 
 fn camera_zoom(
-    mut query: Query<(&mut PanCam, &mut Transform)>,
+    mut query: Query<&mut PanCam>,
     mut scroll_events: EventReader<MouseWheel>,
     mut pinch_events: EventReader<PinchGesture>,
+    hover_map: Res<HoverMap>,
+    ui_nodes: Query<(), With<ComputedNode>>,
 ) {
+    // Scrolling over the UI scrolls the UI rather than zooming.
+    let over_ui = hover_map
+        .values()
+        .flat_map(|hits| hits.keys())
+        .any(|entity| ui_nodes.contains(*entity));
+
     let pixels_per_line = 100.;
     let mut scroll = scroll_events
         .read()
+        .filter(|_| !over_ui)
         .map(|ev| match ev.unit {
             MouseScrollUnit::Pixel => ev.y,
             MouseScrollUnit::Line => ev.y * pixels_per_line,
@@ -51,7 +80,7 @@ fn camera_zoom(
         return;
     }
 
-    for (mut cam, mut pos) in &mut query {
+    for mut cam in &mut query {
         let anim_start_pos = Vec3::new(-2.0, 1.0, 6.0);
         let anim_start_transform =
             Transform::from_translation(anim_start_pos).looking_at(Vec3::ZERO, Vec3::Y);
@@ -71,7 +100,7 @@ fn camera_zoom(
         cam.current_zoom = (cam.current_zoom - scroll / 500.).clamp(0., 1.0);
 
         let actual_zoom = ((cam.current_zoom - 0.2) / 0.8).clamp(0.0, 1.0);
-        let anim_progress = (cam.current_zoom / 0.2).clamp(0.0, 1.0);
+        let anim_progress = cam.anim_progress();
 
         let (bounds_min, bounds_max) = if anim_progress < 1. {
             (
@@ -90,6 +119,7 @@ fn camera_zoom(
 
         let rot = anim_start_rotation.slerp(anim_end_rotation, anim_progress);
 
+        let pos = &mut cam.view;
         pos.translation.x = pos.translation.x.max(bounds_min.x).min(bounds_max.x);
         pos.translation.y = pos.translation.y.max(bounds_min.y).min(bounds_max.y);
         pos.translation.z = max_z.lerp(min_z, actual_zoom);
@@ -98,27 +128,53 @@ fn camera_zoom(
 }
 
 fn camera_movement(
-    mut query: Query<(&mut PanCam, &mut Transform)>,
+    mut query: Query<&mut PanCam>,
     mut drag_events: EventReader<Pointer<Drag>>,
+    dragged_hold: Res<DraggedHold>,
 ) {
+    if dragged_hold.is_dragging() || dragged_hold.is_changed() {
+        drag_events.clear();
+        return;
+    }
+
     for e in drag_events.read() {
-        for (cam, mut transform) in &mut query {
+        for mut cam in &mut query {
             // TODO observed board movement should be 1-1 with cursor movement
             let delta = e.event.delta * Vec2::new(1., -1.) / cam.bounds_max.x / 40.;
-            let proposed_cam_transform = transform.translation - delta.extend(0.);
+            let proposed_cam_transform = cam.view.translation - delta.extend(0.);
 
+            let (bounds_min, bounds_max) = (cam.bounds_min, cam.bounds_max);
+            let transform = &mut cam.view;
             transform.translation = proposed_cam_transform;
-            transform.translation.x = transform
-                .translation
-                .x
-                .max(cam.bounds_min.x)
-                .min(cam.bounds_max.x);
-            transform.translation.y = transform
-                .translation
-                .y
-                .max(cam.bounds_min.y)
-                .min(cam.bounds_max.y);
+            transform.translation.x = transform.translation.x.max(bounds_min.x).min(bounds_max.x);
+            transform.translation.y = transform.translation.y.max(bounds_min.y).min(bounds_max.y);
+        }
+    }
+}
+
+/// Places the camera relative to the board's center, as if the board were
+/// upright. Zooming in turns the camera to face the tilted board, so panning
+/// and the bounds follow its surface.
+fn follow_board(
+    mut cameras: Query<(Ref<PanCam>, &mut Transform), (Without<BoardPivot>, Without<Board>)>,
+    pivots: Query<Ref<Transform>, With<BoardPivot>>,
+    boards: Query<Ref<Transform>, With<Board>>,
+) {
+    let (Ok(pivot), Ok(board)) = (pivots.single(), boards.single()) else {
+        return;
+    };
+
+    let center = pivot.transform_point(board.translation);
+
+    for (cam, mut transform) in &mut cameras {
+        if !cam.is_changed() && !pivot.is_changed() && !board.is_changed() {
+            continue;
         }
+
+        let tilt = Quat::IDENTITY.slerp(pivot.rotation, cam.anim_progress());
+
+        transform.translation = center + tilt * cam.view.translation;
+        transform.rotation = tilt * cam.view.rotation;
     }
 }
 
@@ -127,4 +183,14 @@ struct PanCam {
     current_zoom: f32,
     bounds_min: Vec2,
     bounds_max: Vec2,
+    /// The camera's transform relative to the board's center, as if the board
+    /// were upright.
+    view: Transform,
+}
+impl PanCam {
+    /// How far through the animation from the overview to facing the board
+    /// the camera is.
+    fn anim_progress(&self) -> f32 {
+        (self.current_zoom / 0.2).clamp(0.0, 1.0)
+    }
 }
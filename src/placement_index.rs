@@ -0,0 +1,239 @@
+use bevy::{platform::collections::HashMap, prelude::*};
+
+use crate::{
+    kilter_board::{ActiveLayout, KilterSettings},
+    kilter_data::KilterData,
+};
+
+/// How far, in world units, a point can be from a placement and still pick
+/// it. About the distance between neighbouring holes.
+pub const MAX_SNAP_RADIUS: f32 = 0.1;
+
+/// Finds placements on the active layout by position without scanning them
+/// all.
+pub struct PlacementIndexPlugin;
+
+impl Plugin for PlacementIndexPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PlacementIndex>()
+            .add_systems(PreUpdate, rebuild);
+    }
+}
+
+/// The positions of the placements shown on the active layout, relative to
+/// the board, bucketed into a grid.
+#[derive(Resource, Default)]
+pub struct PlacementIndex {
+    positions: HashMap<u32, Vec2>,
+    cells: HashMap<IVec2, Vec<u32>>,
+}
+impl PlacementIndex {
+    const CELL_SIZE: f32 = MAX_SNAP_RADIUS;
+
+    fn cell(pos: Vec2) -> IVec2 {
+        (pos / Self::CELL_SIZE).floor().as_ivec2()
+    }
+
+    fn build(kilter: &KilterData, settings: &KilterSettings, active: &ActiveLayout) -> Self {
+        let mut index = Self::default();
+
+        for (id, placement) in &kilter.placements {
+            if !active.shows_placement(kilter, *id) {
+                continue;
+            }
+
+            let Some(hole) = kilter.holes.get(&placement.hole_id) else {
+                continue;
+            };
+
+            let pos = Vec2::new(hole.x as f32, hole.y as f32) * settings.scale + settings.offset;
+
+            index.positions.insert(*id, pos);
+            index.cells.entry(Self::cell(pos)).or_default().push(*id);
+        }
+
+        index
+    }
+
+    /// The position of `placement_id` relative to the board, if it's on the
+    /// active layout.
+    pub fn position(&self, placement_id: u32) -> Option<Vec2> {
+        self.positions.get(&placement_id).copied()
+    }
+
+    /// The closest placement to `pos` within [`MAX_SNAP_RADIUS`], skipping any
+    /// that aren't `available`.
+    pub fn nearest(&self, pos: Vec2, available: impl Fn(u32) -> bool) -> Option<u32> {
+        let center = Self::cell(pos);
+        let reach = (MAX_SNAP_RADIUS / Self::CELL_SIZE).ceil() as i32;
+
+        let mut min: Option<(u32, f32)> = None;
+
+        for x in -reach..=reach {
+            for y in -reach..=reach {
+                let Some(ids) = self.cells.get(&(center + IVec2::new(x, y))) else {
+                    continue;
+                };
+
+                for id in ids {
+                    let d_squared = self.positions[id].distance_squared(pos);
+
+                    if d_squared > MAX_SNAP_RADIUS * MAX_SNAP_RADIUS || !available(*id) {
+                        continue;
+                    }
+
+                    // Break ties by id so that picking doesn't depend on
+                    // iteration order.
+                    if min.is_none_or(|(min_id, min_d_squared)| {
+                        (d_squared, *id) < (min_d_squared, min_id)
+                    }) {
+                        min = Some((*id, d_squared));
+                    }
+                }
+            }
+        }
+
+        min.map(|(id, _)| id)
+    }
+}
+
+/// Rebuilds the index when the board or the placements on it change, but not
+/// when only climbs do.
+fn rebuild(
+    mut index: ResMut<PlacementIndex>,
+    mut built_generation: Local<Option<u64>>,
+    kilter: Res<KilterData>,
+    settings: Res<KilterSettings>,
+    active: Res<ActiveLayout>,
+) {
+    let generation = Some(kilter.placements_generation());
+    if generation == *built_generation && !settings.is_changed() && !active.is_changed() {
+        return;
+    }
+
+    *built_generation = generation;
+    *index = PlacementIndex::build(&kilter, &settings, &active);
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::kilter_data::Climb;
+
+    /// Holes a tenth of a world unit apart, so hole coordinates are world
+    /// positions times ten.
+    fn settings() -> KilterSettings {
+        KilterSettings {
+            offset: Vec2::ZERO,
+            scale: 0.1,
+        }
+    }
+
+    /// Placements on layout 1 at `(x, y)` with matching ids, and one on
+    /// layout 2 at the origin.
+    fn kilter(holes: &[(u32, i32, i32)]) -> KilterData {
+        let mut kilter = KilterData::default();
+        let mut placements =
+            vec![json!({ "id": 100, "layout_id": 2, "hole_id": 100, "set_id": 1 })];
+        let mut hole_records = vec![json!({ "id": 100, "product_id": 1, "name": "", "x": 0,
+            "y": 0, "mirrored_hole_id": 0, "mirror_group": 0 })];
+        for &(id, x, y) in holes {
+            placements.push(json!({ "id": id, "layout_id": 1, "hole_id": id, "set_id": 1 }));
+            hole_records.push(
+                json!({ "id": id, "product_id": 1, "name": "", "x": x, "y": y,
+                "mirrored_hole_id": 0, "mirror_group": 0 }),
+            );
+        }
+
+        kilter
+            .json_update(&json!({ "PUT": { "placements": placements, "holes": hole_records } }))
+            .unwrap();
+        kilter
+    }
+
+    fn index(holes: &[(u32, i32, i32)]) -> PlacementIndex {
+        PlacementIndex::build(&kilter(holes), &settings(), &ActiveLayout::default())
+    }
+
+    #[test]
+    fn finds_nearest_within_snap_radius() {
+        let index = index(&[(1, 0, 0), (2, 1, 0), (3, 5, 5)]);
+
+        assert_eq!(index.nearest(Vec2::new(0.03, 0.), |_| true), Some(1));
+        assert_eq!(index.nearest(Vec2::new(0.07, 0.), |_| true), Some(2));
+        assert_eq!(index.nearest(Vec2::new(0.52, 0.48), |_| true), Some(3));
+        assert_eq!(index.position(3), Some(Vec2::new(0.5, 0.5)));
+    }
+
+    #[test]
+    fn misses_outside_snap_radius() {
+        let index = index(&[(1, 0, 0)]);
+
+        assert_eq!(index.nearest(Vec2::new(0.11, 0.), |_| true), None);
+        assert_eq!(index.nearest(Vec2::new(0.08, 0.08), |_| true), None);
+        assert_eq!(index.nearest(Vec2::new(-3., 2.), |_| true), None);
+    }
+
+    #[test]
+    fn skips_unavailable_placements() {
+        let index = index(&[(1, 0, 0), (2, 1, 0)]);
+
+        assert_eq!(index.nearest(Vec2::new(0.02, 0.), |id| id != 1), Some(2));
+        assert_eq!(index.nearest(Vec2::new(0.02, 0.), |_| false), None);
+    }
+
+    #[test]
+    fn only_indexes_active_layout() {
+        let index = index(&[(1, 5, 5)]);
+
+        // Placement 100 is at the origin on layout 2.
+        assert_eq!(index.position(100), None);
+        assert_eq!(index.nearest(Vec2::ZERO, |_| true), None);
+    }
+
+    #[test]
+    fn breaks_ties_by_id() {
+        // Inserted so that the higher id comes first.
+        let index = index(&[(2, 1, 0), (1, -1, 0)]);
+
+        assert_eq!(index.nearest(Vec2::ZERO, |_| true), Some(1));
+    }
+
+    #[test]
+    fn rebuilds_only_when_placements_change() {
+        let mut app = App::new();
+        app.insert_resource(kilter(&[(1, 0, 0)]))
+            .init_resource::<PlacementIndex>()
+            .init_resource::<ActiveLayout>()
+            .insert_resource(settings())
+            .add_systems(Update, rebuild);
+        app.update();
+        assert_eq!(
+            app.world().resource::<PlacementIndex>().position(1),
+            Some(Vec2::ZERO)
+        );
+
+        // Clear the index to tell whether it's rebuilt.
+        *app.world_mut().resource_mut::<PlacementIndex>() = PlacementIndex::default();
+        app.world_mut()
+            .resource_mut::<KilterData>()
+            .insert_climb(Climb {
+                uuid: "a".to_string(),
+                ..Default::default()
+            });
+        app.update();
+        assert_eq!(app.world().resource::<PlacementIndex>().position(1), None);
+
+        app.world_mut()
+            .resource_mut::<KilterData>()
+            .json_update(&json!({ "PATCH": { "holes": [{ "id": 1, "x": 2 }] } }))
+            .unwrap();
+        app.update();
+        assert_eq!(
+            app.world().resource::<PlacementIndex>().position(1),
+            Some(Vec2::new(0.2, 0.))
+        );
+    }
+}
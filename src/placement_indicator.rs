@@ -1,7 +1,9 @@
 use bevy::{ecs::system::SystemParam, platform::collections::HashMap, prelude::*};
 
-use crate::{kilter_board::KilterSettings, kilter_data::KilterData};
-use std::fmt::Display;
+use crate::{
+    kilter_board::KilterSettings,
+    kilter_data::{FramePlacement, KilterData},
+};
 
 pub struct PlacementIndicatorPlugin;
 
@@ -55,9 +57,12 @@ pub struct PlacementIndicator {
     pub placement_id: u32,
     pub role_id: u32,
 }
-impl Display for PlacementIndicator {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "p{}r{}", self.placement_id, self.role_id)
+impl From<&PlacementIndicator> for FramePlacement {
+    fn from(indicator: &PlacementIndicator) -> Self {
+        Self {
+            placement_id: indicator.placement_id,
+            role_id: indicator.role_id,
+        }
     }
 }
 
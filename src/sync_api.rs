@@ -0,0 +1,560 @@
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use bevy::prelude::*;
+use bevy_http_client::prelude::*;
+use serde_derive::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+    data_loading::{AppliedUpdates, DataState},
+    kilter_data::{open_database, KilterData, KilterDataError, DATABASE_PATH, UPDATES_PATH},
+    ui::toast_panel::ToastEvent,
+};
+
+/// Where the sync cursor is kept between runs by default. Synced updates are
+/// saved to [`UPDATES_PATH`], to be loaded along with the database on the next
+/// run.
+const CURSOR_PATH: &str = "../kilter_brain_data/sync.json";
+
+/// Tables to keep up to date, in the order the API expects them.
+const SHARED_TABLES: [&str; 12] = [
+    "products",
+    "product_sizes",
+    "holes",
+    "leds",
+    "products_angles",
+    "layouts",
+    "product_sizes_layouts_sets",
+    "placements",
+    "sets",
+    "placement_roles",
+    "climbs",
+    "climb_stats",
+];
+
+/// Synced before the first sync of a table.
+const EPOCH: &str = "1970-01-01 00:00:00.000000";
+
+/// Fetches changes from the Kilter Board API on startup, starting where the
+/// last sync left off, when [`SyncApiSettings::enabled`].
+pub struct SyncApiPlugin;
+
+impl Plugin for SyncApiPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SyncApiSettings>()
+            .init_resource::<SyncCursor>()
+            .register_type::<SyncApiSettings>()
+            .register_request_type::<SyncResponse>()
+            .add_systems(OnEnter(DataState::Ready), start_sync)
+            .add_systems(Update, (handle_response, handle_error));
+    }
+}
+
+#[derive(Reflect, Resource)]
+#[reflect(Resource)]
+pub struct SyncApiSettings {
+    /// Off unless the app is run with `--sync`, so that running it doesn't
+    /// contact the API unasked.
+    pub enabled: bool,
+    /// Set `KILTER_SYNC_HOST` to sync from somewhere else, e.g. a local mock
+    /// server.
+    pub host: String,
+    /// Where synced updates are saved.
+    pub updates_path: PathBuf,
+    /// Where the sync cursor is saved.
+    pub cursor_path: PathBuf,
+}
+impl Default for SyncApiSettings {
+    fn default() -> Self {
+        Self {
+            enabled: std::env::args().any(|arg| arg == "--sync"),
+            host: std::env::var("KILTER_SYNC_HOST")
+                .unwrap_or_else(|_| "https://kilterboardapp.com".to_string()),
+            updates_path: UPDATES_PATH.into(),
+            cursor_path: CURSOR_PATH.into(),
+        }
+    }
+}
+
+/// When each table was last synced, as reported by the API.
+#[derive(Resource, Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct SyncCursor(pub BTreeMap<String, String>);
+impl SyncCursor {
+    /// Loads the cursor saved by the last sync, or else the one recorded in
+    /// the database.
+    fn load(path: &Path) -> Self {
+        match fs::read_to_string(path) {
+            Ok(json) => match serde_json::from_str(&json) {
+                Ok(cursor) => return cursor,
+                Err(e) => warn!("Failed to read sync cursor. {}", e),
+            },
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => warn!("Failed to read sync cursor. {}", e),
+        }
+
+        Self::from_database(DATABASE_PATH).unwrap_or_default()
+    }
+
+    /// Reads the `shared_syncs` table of a Kilter Board app database.
+    fn from_database(path: &str) -> Result<Self, KilterDataError> {
+        let conn = open_database(path)?;
+        let mut stmt = conn.prepare("SELECT table_name, last_synchronized_at FROM shared_syncs")?;
+        let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+
+        Ok(Self(rows.collect::<rusqlite::Result<_>>()?))
+    }
+
+    fn save(&self, path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// The form body of a `/sync` request for every table in
+    /// [`SHARED_TABLES`].
+    pub fn request_body(&self) -> String {
+        let mut fields = vec![
+            ("client[enforces_product_passwords]", "1"),
+            ("client[enforces_layout_passwords]", "1"),
+            ("client[manages_power_responsibly]", "1"),
+            ("client[ufd]", "1"),
+        ];
+
+        for table in SHARED_TABLES {
+            let synced_at = self.0.get(table).map(String::as_str).unwrap_or(EPOCH);
+            fields.push(("shared_sync[][table_name]", table));
+            fields.push(("shared_sync[][last_synchronized_at]", synced_at));
+        }
+
+        fields
+            .into_iter()
+            .map(|(key, value)| format!("{}={}", form_encode(key), form_encode(value)))
+            .collect::<Vec<_>>()
+            .join("&")
+    }
+
+    /// Records the sync times from a response.
+    fn update(&mut self, response: &Value) {
+        let Some(syncs) = response.get("shared_syncs").and_then(Value::as_array) else {
+            return;
+        };
+
+        for sync in syncs {
+            if let (Some(table), Some(synced_at)) = (
+                sync.get("table_name").and_then(Value::as_str),
+                sync.get("last_synchronized_at").and_then(Value::as_str),
+            ) {
+                self.0.insert(table.to_string(), synced_at.to_string());
+            }
+        }
+    }
+}
+
+/// Records by table name, plus `shared_syncs` and whether there's more to
+/// fetch.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(transparent)]
+pub struct SyncResponse(pub Value);
+impl SyncResponse {
+    /// The API sends large updates a page at a time.
+    fn is_complete(&self) -> bool {
+        self.0
+            .get("_complete")
+            .and_then(Value::as_bool)
+            .unwrap_or(true)
+    }
+}
+
+/// Percent-encodes `value` for an `application/x-www-form-urlencoded` body.
+fn form_encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'*' => {
+                (b as char).to_string()
+            }
+            b' ' => "+".to_string(),
+            b => format!("%{b:02X}"),
+        })
+        .collect()
+}
+
+/// Saves `response` as a JSON update in `dir`, named so that it sorts after
+/// those saved before it.
+fn save_update(dir: &Path, response: &Value) -> Result<(), Box<dyn std::error::Error>> {
+    let mut millis = SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis();
+    let path = loop {
+        // Pages can arrive within the same millisecond.
+        let path = dir.join(format!("sync_{millis:020}.json"));
+        if !path.exists() {
+            break path;
+        }
+        millis += 1;
+    };
+
+    fs::create_dir_all(dir)?;
+    fs::write(
+        path,
+        serde_json::to_string(&serde_json::json!({ "PUT": response }))?,
+    )?;
+    Ok(())
+}
+
+fn sync_request(settings: &SyncApiSettings, cursor: &SyncCursor) -> TypedRequest<SyncResponse> {
+    let mut request = HttpClient::new()
+        .post(format!("{}/sync", settings.host))
+        .headers(&[
+            ("Content-Type", "application/x-www-form-urlencoded"),
+            ("Accept", "application/json"),
+        ])
+        .build()
+        .request;
+    request.body = cursor.request_body().into_bytes();
+
+    TypedRequest::new(request, None)
+}
+
+fn start_sync(
+    settings: Res<SyncApiSettings>,
+    mut cursor: ResMut<SyncCursor>,
+    mut requests: EventWriter<TypedRequest<SyncResponse>>,
+) {
+    if !settings.enabled {
+        return;
+    }
+
+    *cursor = SyncCursor::load(&settings.cursor_path);
+
+    requests.write(sync_request(&settings, &cursor));
+}
+
+/// What to do after a page of a sync.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum SyncStep {
+    /// Ask for the next page from the moved cursor.
+    Next,
+    Done,
+    /// The API sent more to fetch without moving the cursor.
+    Stalled,
+    /// The page couldn't be applied or saved, with a message for the user.
+    Failed(String),
+}
+
+/// Applies and saves a page of a sync, moving the cursor past it.
+fn sync_page(
+    response: &SyncResponse,
+    kilter: &mut KilterData,
+    applied: &mut AppliedUpdates,
+    cursor: &mut SyncCursor,
+    settings: &SyncApiSettings,
+) -> SyncStep {
+    let report = match kilter.json_update_tables(&response.0) {
+        Ok(report) => report,
+        Err(e) => {
+            warn!("Failed to apply sync. {}", e);
+            return SyncStep::Failed(format!("Sync failed: {e}"));
+        }
+    };
+
+    applied.0.push(response.0.clone());
+
+    for e in &report.errors {
+        warn!("Skipped synced record. {}", e);
+    }
+    for (table, changes) in &report.changes {
+        info!("Synced {}: {}", table, changes);
+    }
+
+    // Only move the cursor past updates that will be loaded again. Asking for
+    // the next page without moving it would fetch this page forever.
+    if let Err(e) = save_update(&settings.updates_path, &response.0) {
+        warn!("Failed to save synced update. {}", e);
+        return SyncStep::Failed("Sync failed: couldn't save update".to_string());
+    }
+
+    let previous = cursor.clone();
+    cursor.update(&response.0);
+    if let Err(e) = cursor.save(&settings.cursor_path) {
+        warn!("Failed to save sync cursor. {}", e);
+    }
+
+    if response.is_complete() {
+        SyncStep::Done
+    } else if *cursor == previous {
+        SyncStep::Stalled
+    } else {
+        SyncStep::Next
+    }
+}
+
+fn handle_response(
+    mut responses: EventReader<TypedResponse<SyncResponse>>,
+    mut kilter: ResMut<KilterData>,
+    mut applied: ResMut<AppliedUpdates>,
+    mut cursor: ResMut<SyncCursor>,
+    settings: Res<SyncApiSettings>,
+    mut requests: EventWriter<TypedRequest<SyncResponse>>,
+    mut toasts: EventWriter<ToastEvent>,
+) {
+    for response in responses.read() {
+        match sync_page(response, &mut kilter, &mut applied, &mut cursor, &settings) {
+            SyncStep::Next => {
+                requests.write(sync_request(&settings, &cursor));
+            }
+            SyncStep::Done => {
+                toasts.write(ToastEvent("Synced with Kilter Board".to_string()));
+            }
+            SyncStep::Stalled => {
+                warn!("Sync stopped. The API sent more to fetch without moving the cursor.");
+                toasts.write(ToastEvent("Sync stopped early".to_string()));
+            }
+            SyncStep::Failed(message) => {
+                toasts.write(ToastEvent(message));
+            }
+        }
+    }
+}
+
+fn handle_error(
+    mut errors: EventReader<TypedResponseError<SyncResponse>>,
+    mut toasts: EventWriter<ToastEvent>,
+) {
+    for error in errors.read() {
+        warn!("Sync failed. {}", error.err);
+        toasts.write(ToastEvent("Sync failed".to_string()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn encodes_form_values() {
+        assert_eq!(form_encode("abc-XYZ_0.9*"), "abc-XYZ_0.9*");
+        assert_eq!(
+            form_encode("shared_sync[][table_name]"),
+            "shared_sync%5B%5D%5Btable_name%5D"
+        );
+        assert_eq!(
+            form_encode("2024-01-02 03:04:05.678"),
+            "2024-01-02+03%3A04%3A05.678"
+        );
+        assert_eq!(form_encode("é&="), "%C3%A9%26%3D");
+    }
+
+    #[test]
+    fn requests_every_table_from_the_cursor() {
+        let cursor = SyncCursor(BTreeMap::from([(
+            "climbs".to_string(),
+            "2024-01-02 03:04:05.678".to_string(),
+        )]));
+
+        let body = cursor.request_body();
+        let fields: Vec<_> = body.split('&').collect();
+
+        assert_eq!(fields[0], "client%5Benforces_product_passwords%5D=1");
+        assert_eq!(fields.len(), 4 + SHARED_TABLES.len() * 2);
+
+        let tables: Vec<_> = fields[4..]
+            .chunks(2)
+            .map(|pair| {
+                let table = pair[0].strip_prefix("shared_sync%5B%5D%5Btable_name%5D=");
+                let synced_at =
+                    pair[1].strip_prefix("shared_sync%5B%5D%5Blast_synchronized_at%5D=");
+                (table.unwrap(), synced_at.unwrap())
+            })
+            .collect();
+
+        assert_eq!(
+            tables.iter().map(|(table, _)| *table).collect::<Vec<_>>(),
+            SHARED_TABLES
+        );
+        for (table, synced_at) in tables {
+            let expected = if table == "climbs" {
+                "2024-01-02+03%3A04%3A05.678"
+            } else {
+                "1970-01-01+00%3A00%3A00.000000"
+            };
+            assert_eq!(synced_at, expected, "{table}");
+        }
+    }
+
+    #[test]
+    fn records_sync_times_from_responses() {
+        let mut cursor = SyncCursor(BTreeMap::from([
+            ("climbs".to_string(), "old".to_string()),
+            ("holes".to_string(), "old".to_string()),
+        ]));
+
+        cursor.update(&json!({
+            "climbs": [],
+            "shared_syncs": [
+                { "table_name": "climbs", "last_synchronized_at": "new" },
+                { "table_name": "leds", "last_synchronized_at": "newer" },
+                { "table_name": "sets" },
+            ],
+        }));
+
+        assert_eq!(
+            cursor.0,
+            BTreeMap::from([
+                ("climbs".to_string(), "new".to_string()),
+                ("holes".to_string(), "old".to_string()),
+                ("leds".to_string(), "newer".to_string()),
+            ])
+        );
+
+        // Responses without sync times leave the cursor alone.
+        let before = cursor.clone();
+        cursor.update(&json!({ "climbs": [] }));
+        assert_eq!(cursor, before);
+    }
+
+    /// Settings that save to a fresh directory for `test`.
+    fn temp_settings(test: &str) -> SyncApiSettings {
+        let dir = std::env::temp_dir().join(format!("kilter_sync_{test}_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+
+        SyncApiSettings {
+            enabled: true,
+            host: "http://localhost".to_string(),
+            updates_path: dir.join("api_json"),
+            cursor_path: dir.join("sync.json"),
+        }
+    }
+
+    fn page(uuid: &str, synced_at: &str, complete: bool) -> Value {
+        let climb = serde_json::to_value(crate::kilter_data::Climb {
+            uuid: uuid.to_string(),
+            ..Default::default()
+        })
+        .unwrap();
+
+        json!({
+            "climbs": [climb],
+            "shared_syncs": [{ "table_name": "climbs", "last_synchronized_at": synced_at }],
+            "_complete": complete,
+        })
+    }
+
+    /// Runs the paging loop against `transport`, which answers request bodies
+    /// like the API would, returning the bodies sent and the last step.
+    fn run_sync(
+        settings: &SyncApiSettings,
+        kilter: &mut KilterData,
+        applied: &mut AppliedUpdates,
+        mut transport: impl FnMut(&str) -> Value,
+    ) -> (Vec<String>, SyncStep) {
+        let mut cursor = SyncCursor::default();
+        let mut bodies = vec![];
+
+        loop {
+            let body = cursor.request_body();
+            let response = SyncResponse(transport(&body));
+            bodies.push(body);
+
+            let step = sync_page(&response, kilter, applied, &mut cursor, settings);
+            if step != SyncStep::Next || bodies.len() > 10 {
+                return (bodies, step);
+            }
+        }
+    }
+
+    /// Answers with the page for the cursor the request asks for climbs from.
+    fn canned<'a>(pages: &'a [(&'a str, Value)]) -> impl FnMut(&str) -> Value + 'a {
+        move |body| {
+            let (_, page) = pages
+                .iter()
+                .find(|(from, _)| {
+                    body.contains(&format!(
+                        "table_name%5D=climbs&shared_sync%5B%5D%5Blast_synchronized_at%5D={}",
+                        form_encode(from)
+                    ))
+                })
+                .expect("a page for the cursor");
+            page.clone()
+        }
+    }
+
+    #[test]
+    fn pages_until_complete() {
+        let settings = temp_settings("pages");
+        let mut kilter = KilterData::default();
+        let mut applied = AppliedUpdates::default();
+
+        let pages = [
+            (EPOCH, page("a", "2024-01-01 00:00:00.000000", false)),
+            (
+                "2024-01-01 00:00:00.000000",
+                page("b", "2024-02-01 00:00:00.000000", true),
+            ),
+        ];
+        let (bodies, step) = run_sync(&settings, &mut kilter, &mut applied, canned(&pages));
+
+        assert_eq!(step, SyncStep::Done);
+        assert_eq!(bodies.len(), 2);
+        assert!(kilter.climb("a").is_some() && kilter.climb("b").is_some());
+        assert_eq!(applied.0.len(), 2);
+
+        let saved: Vec<_> = fs::read_dir(&settings.updates_path).unwrap().collect();
+        assert_eq!(saved.len(), 2);
+        assert_eq!(
+            SyncCursor::load(&settings.cursor_path).0["climbs"],
+            "2024-02-01 00:00:00.000000"
+        );
+
+        fs::remove_dir_all(settings.cursor_path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn stops_when_the_cursor_doesnt_move() {
+        let settings = temp_settings("stalls");
+        let mut kilter = KilterData::default();
+        let mut applied = AppliedUpdates::default();
+
+        let pages = [
+            (EPOCH, page("a", "2024-01-01 00:00:00.000000", false)),
+            (
+                "2024-01-01 00:00:00.000000",
+                page("b", "2024-01-01 00:00:00.000000", false),
+            ),
+        ];
+        let (bodies, step) = run_sync(&settings, &mut kilter, &mut applied, canned(&pages));
+
+        assert_eq!(step, SyncStep::Stalled);
+        assert_eq!(bodies.len(), 2);
+        assert!(kilter.climb("b").is_some());
+        assert_eq!(
+            SyncCursor::load(&settings.cursor_path).0["climbs"],
+            "2024-01-01 00:00:00.000000"
+        );
+
+        fs::remove_dir_all(settings.cursor_path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn fails_without_moving_the_cursor_when_updates_cant_be_saved() {
+        let settings = temp_settings("unsaved");
+        // A file where the updates directory should be.
+        fs::write(&settings.updates_path, "").unwrap();
+        let mut kilter = KilterData::default();
+        let mut applied = AppliedUpdates::default();
+
+        let pages = [(EPOCH, page("a", "2024-01-01 00:00:00.000000", false))];
+        let (bodies, step) = run_sync(&settings, &mut kilter, &mut applied, canned(&pages));
+
+        assert!(matches!(step, SyncStep::Failed(_)));
+        assert_eq!(bodies.len(), 1);
+        assert!(!settings.cursor_path.exists());
+
+        fs::remove_dir_all(settings.cursor_path.parent().unwrap()).unwrap();
+    }
+}
@@ -1,17 +1,17 @@
 use bevy::prelude::*;
 use bevy_http_client::{prelude::TypedRequest, HttpClient};
 use serde::Serialize;
-use std::fmt::Write;
 use uuid::Uuid;
 
 use crate::{
     gen_api::{GenApiSettings, GeneratedClimb, GeneratedClimbs},
-    kilter_board::{BoardAngle, SelectedClimb},
-    kilter_data::{Climb, KilterData},
+    history::{Edit, EditEvent, HistoryEvent},
+    kilter_board::{ActiveLayout, BoardAngle, FramePlayback, SelectedClimb},
+    kilter_data::{Climb, ClimbOrigin, Frame, FramePlacement, HoldFilter, HoldMatch, KilterData},
     placement_indicator::PlacementIndicator,
 };
 
-use super::{button::button, theme};
+use super::{button::button, filter_panel::ClimbFilter, theme, toast_panel::ToastEvent};
 
 pub struct ActionPanelPlugin;
 
@@ -20,6 +20,12 @@ struct NewButton;
 #[derive(Component)]
 struct ClearButton;
 #[derive(Component)]
+struct UndoButton;
+#[derive(Component)]
+struct RedoButton;
+#[derive(Component)]
+struct MirrorButton;
+#[derive(Component)]
 struct GenButton;
 #[derive(Component)]
 struct GenNewButton;
@@ -27,6 +33,8 @@ struct GenNewButton;
 struct PublishButton;
 #[derive(Component)]
 struct OpenClimbButton;
+#[derive(Component)]
+struct FindButton;
 
 #[derive(Serialize)]
 struct GenerateRequest {
@@ -41,11 +49,15 @@ impl Plugin for ActionPanelPlugin {
             Update,
             (
                 clear_button,
+                undo_button,
+                redo_button,
+                mirror_button,
                 new_button,
                 gen_fill_button,
                 gen_new_button,
                 publish_button,
                 open_climb_button,
+                find_button,
             ),
         );
     }
@@ -79,18 +91,26 @@ fn setup_buttons_panel(mut commands: Commands) {
 
     let new_button = button(&mut commands, "New", NewButton);
     let clear_button = button(&mut commands, "Clear", ClearButton);
+    let undo_button = button(&mut commands, "Undo", UndoButton);
+    let redo_button = button(&mut commands, "Redo", RedoButton);
+    let mirror_button = button(&mut commands, "Mirror", MirrorButton);
     let gen_button = button(&mut commands, "Gen Fill", GenButton);
     let gen_new_button = button(&mut commands, "Gen New", GenNewButton);
     let publish_button = button(&mut commands, "Publish", PublishButton);
     let open_climb_button = button(&mut commands, "Open", OpenClimbButton);
+    let find_button = button(&mut commands, "Find", FindButton);
 
     commands.entity(container).add_children(&[
         new_button,
         clear_button,
+        undo_button,
+        redo_button,
+        mirror_button,
         gen_button,
         gen_new_button,
         publish_button,
         open_climb_button,
+        find_button,
     ]);
 
     commands.entity(root).add_child(container);
@@ -98,13 +118,79 @@ fn setup_buttons_panel(mut commands: Commands) {
 
 fn clear_button(
     query: Query<&Interaction, (With<ClearButton>, Changed<Interaction>)>,
-    mut commands: Commands,
-    placement_query: Query<Entity, With<PlacementIndicator>>,
+    kilter: Res<KilterData>,
+    selected: Res<SelectedClimb>,
+    playback: Res<FramePlayback>,
+    mut edits: EventWriter<EditEvent>,
+) {
+    if query.iter().any(|i| *i == Interaction::Pressed) {
+        let Some((uuid, climb)) = kilter.climb_at(selected.0) else {
+            return;
+        };
+
+        let Some(holds) = climb.frames.get(playback.frame).filter(|f| !f.is_empty()) else {
+            return;
+        };
+
+        edits.write(EditEvent(Edit::Clear {
+            uuid: uuid.clone(),
+            frame: playback.frame,
+            holds: holds.clone(),
+            origin: climb.origin,
+        }));
+    }
+}
+
+fn undo_button(
+    query: Query<&Interaction, (With<UndoButton>, Changed<Interaction>)>,
+    mut events: EventWriter<HistoryEvent>,
+) {
+    if query.iter().any(|i| *i == Interaction::Pressed) {
+        events.write(HistoryEvent::Undo);
+    }
+}
+
+fn redo_button(
+    query: Query<&Interaction, (With<RedoButton>, Changed<Interaction>)>,
+    mut events: EventWriter<HistoryEvent>,
+) {
+    if query.iter().any(|i| *i == Interaction::Pressed) {
+        events.write(HistoryEvent::Redo);
+    }
+}
+
+/// Adds a copy of the selected climb flipped left to right.
+fn mirror_button(
+    query: Query<&Interaction, (With<MirrorButton>, Changed<Interaction>)>,
+    kilter: Res<KilterData>,
+    selected: Res<SelectedClimb>,
+    mut edits: EventWriter<EditEvent>,
+    mut toasts: EventWriter<ToastEvent>,
 ) {
     if query.iter().any(|i| *i == Interaction::Pressed) {
-        for entity in &placement_query {
-            commands.entity(entity).despawn();
+        let Some((uuid, climb)) = kilter.climb_at(selected.0) else {
+            return;
+        };
+
+        let mirrored = kilter.mirror_climb(climb);
+
+        if !mirrored.unmirrored.is_empty() {
+            toasts.write(ToastEvent(format!(
+                "No mirror for placements {:?}",
+                mirrored.unmirrored
+            )));
+        }
+
+        if mirrored.climb.frames.iter().all(|f| f.is_empty()) {
+            toasts.write(ToastEvent("Nothing to mirror".to_string()));
+            return;
         }
+
+        edits.write(EditEvent(Edit::AddClimbs {
+            from: uuid.clone(),
+            climbs: vec![mirrored.climb],
+            name: "mirror",
+        }));
     }
 }
 
@@ -112,21 +198,19 @@ fn new_button(
     query: Query<&Interaction, (With<NewButton>, Changed<Interaction>)>,
     mut kilter: ResMut<KilterData>,
     mut selected: ResMut<SelectedClimb>,
+    active: Res<ActiveLayout>,
 ) {
     if query.iter().any(|i| *i == Interaction::Pressed) {
         let id = Uuid::new_v4().to_string();
 
-        kilter.climbs.insert(
-            id.clone(),
-            Climb {
-                uuid: id.clone(),
-                setter_username: "User".to_string(),
-                name: "New Climb".to_string(),
-                ..default()
-            },
-        );
-
-        selected.0 = kilter.climbs.len() - 1;
+        selected.0 = kilter.insert_climb(Climb {
+            uuid: id,
+            setter_username: "User".to_string(),
+            name: "New Climb".to_string(),
+            layout_id: active.layout_id,
+            origin: ClimbOrigin::Authored,
+            ..default()
+        });
     }
 }
 
@@ -159,13 +243,10 @@ fn gen_fill_button(
     angle: Res<BoardAngle>,
 ) {
     if query.iter().any(|i| *i == Interaction::Pressed) {
-        let current_frames: String = indicator_query.iter().fold(String::new(), |mut out, ind| {
-            let _ = write!(out, "{ind}");
-            out
-        });
+        let current_frame: Frame = indicator_query.iter().map(FramePlacement::from).collect();
 
         let request = GenerateRequest {
-            prompt: format!("a{}d20{}", angle.0, current_frames),
+            prompt: format!("a{}d20{}", angle.0, current_frame),
             num: 10,
         };
 
@@ -184,21 +265,37 @@ fn publish_button(
     mut ev_request: EventWriter<TypedRequest<GeneratedClimb>>,
     api_settings: Res<GenApiSettings>,
     selected: Res<SelectedClimb>,
-    kilter: Res<KilterData>,
+    mut kilter: ResMut<KilterData>,
+    playback: Res<FramePlayback>,
+    active: Res<ActiveLayout>,
+    mut toasts: EventWriter<ToastEvent>,
+    mut warned: Local<Option<String>>,
 ) {
     if query.iter().any(|i| *i == Interaction::Pressed) {
-        let current_frames: String = indicator_query.iter().fold(String::new(), |mut out, ind| {
-            let _ = write!(out, "{ind}");
-            out
-        });
+        let current_frame: Frame = indicator_query.iter().map(FramePlacement::from).collect();
 
         // Get selected or first climb
-        let Some((_, climb)) = kilter.climbs.iter().nth(selected.0) else {
+        let Some((_, climb)) = kilter.climbs().iter().nth(selected.0) else {
             return;
         };
 
         let mut new_climb = climb.clone();
-        new_climb.frames = current_frames;
+        new_climb.frames.set(playback.frame, current_frame);
+        new_climb.frames_count = new_climb.frames.len() as u32;
+        new_climb.mark_edited();
+
+        // Warn about broken rules once, then publish anyway if pressed again.
+        let issues = kilter.validate_climb(&new_climb, active.product_size_id);
+        if !issues.is_empty() && warned.as_ref() != Some(&new_climb.uuid) {
+            let issues: Vec<_> = issues.iter().map(ToString::to_string).collect();
+            toasts.write(ToastEvent(format!(
+                "{}. Press Publish again to publish anyway.",
+                issues.join(", ")
+            )));
+            *warned = Some(new_climb.uuid.clone());
+            return;
+        }
+        *warned = None;
 
         ev_request.write(
             HttpClient::new()
@@ -206,6 +303,9 @@ fn publish_button(
                 .json(&new_climb)
                 .with_type::<GeneratedClimb>(),
         );
+
+        // Keep the edit locally too.
+        kilter.insert_climb(new_climb);
     }
 }
 
@@ -215,7 +315,7 @@ fn open_climb_button(
     kilter: Res<KilterData>,
 ) {
     if query.iter().any(|i| *i == Interaction::Pressed) {
-        let Some((_, climb)) = kilter.climbs.iter().nth(selected.0) else {
+        let Some((_, climb)) = kilter.climbs().iter().nth(selected.0) else {
             return;
         };
 
@@ -226,3 +326,21 @@ fn open_climb_button(
         }
     }
 }
+
+/// Searches for climbs using the holds currently on the board.
+fn find_button(
+    query: Query<&Interaction, (With<FindButton>, Changed<Interaction>)>,
+    indicator_query: Query<&PlacementIndicator>,
+    mut filter: ResMut<ClimbFilter>,
+) {
+    if query.iter().any(|i| *i == Interaction::Pressed) {
+        filter.0.holds = indicator_query
+            .iter()
+            .map(|i| HoldFilter {
+                placement_id: i.placement_id,
+                role_id: Some(i.role_id),
+            })
+            .collect();
+        filter.0.hold_match = HoldMatch::All;
+    }
+}
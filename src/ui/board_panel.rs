@@ -1,18 +1,43 @@
 use bevy::prelude::*;
 
-use crate::kilter_board::BoardAngle;
+use crate::{
+    kilter_board::{ActiveLayout, BoardAngle},
+    kilter_data::KilterData,
+};
 
 use super::{button::button, theme};
 
 #[derive(Component)]
 pub struct AngleButton;
+#[derive(Component)]
+pub struct LayoutButton;
+#[derive(Component)]
+pub struct SizeButton;
+/// Holds a button for each set on the active layout.
+#[derive(Component)]
+pub struct SetButtons;
+/// Toggles whether a set is installed on the wall.
+#[derive(Component)]
+pub struct SetButton(u32);
 
 pub struct BoardPanelPlugin;
 
 impl Plugin for BoardPanelPlugin {
     fn build(&self, app: &mut App) {
         app.add_systems(Startup, setup_nav_panel);
-        app.add_systems(Update, (angle_button, angle_button_text));
+        app.add_systems(
+            Update,
+            (
+                angle_button,
+                angle_button_text,
+                layout_button,
+                size_button,
+                layout_buttons_text,
+                update_set_buttons,
+                set_button,
+                set_buttons_text.after(update_set_buttons),
+            ),
+        );
     }
 }
 
@@ -33,8 +58,24 @@ fn setup_nav_panel(mut commands: Commands) {
         .id();
 
     let angle_button = button(&mut commands, "0°", AngleButton);
+    let layout_button = button(&mut commands, "Layout", LayoutButton);
+    let size_button = button(&mut commands, "Size", SizeButton);
+    let set_buttons = commands
+        .spawn((
+            Node {
+                column_gap: Val::Px(12.),
+                ..default()
+            },
+            SetButtons,
+        ))
+        .id();
 
-    commands.entity(container).add_child(angle_button);
+    commands.entity(container).add_children(&[
+        angle_button,
+        layout_button,
+        size_button,
+        set_buttons,
+    ]);
 }
 
 fn angle_button(
@@ -59,3 +100,165 @@ fn angle_button_text(
         text.0 = format!("{}°", angle.0);
     }
 }
+
+fn layout_button(
+    query: Query<&Interaction, (With<LayoutButton>, Changed<Interaction>)>,
+    mut active: ResMut<ActiveLayout>,
+    kilter: Res<KilterData>,
+) {
+    if query.iter().any(|i| *i == Interaction::Pressed) {
+        let layouts: Vec<_> = kilter
+            .sorted_layouts()
+            .into_iter()
+            .filter(|l| l.is_listed)
+            .collect();
+
+        let Some(next) = layouts
+            .iter()
+            .find(|l| l.id > active.layout_id)
+            .or_else(|| layouts.first())
+        else {
+            return;
+        };
+
+        active.layout_id = next.id;
+        active.product_size_id = None;
+    }
+}
+
+fn size_button(
+    query: Query<&Interaction, (With<SizeButton>, Changed<Interaction>)>,
+    mut active: ResMut<ActiveLayout>,
+    kilter: Res<KilterData>,
+) {
+    if query.iter().any(|i| *i == Interaction::Pressed) {
+        let Some(product_id) = active.product_id(&kilter) else {
+            return;
+        };
+
+        let sizes: Vec<_> = kilter
+            .sorted_product_sizes(product_id)
+            .into_iter()
+            .filter(|s| s.is_listed)
+            .map(|s| s.id)
+            .collect();
+
+        // Cycle through each size, then back to showing the whole layout.
+        let current = active
+            .product_size_id
+            .and_then(|id| sizes.iter().position(|s| *s == id));
+
+        active.product_size_id = match current {
+            Some(pos) => sizes.get(pos + 1).copied(),
+            None => sizes.first().copied(),
+        };
+    }
+}
+
+fn layout_buttons_text(
+    active: Res<ActiveLayout>,
+    kilter: Res<KilterData>,
+    mut text_query: Query<&mut Text>,
+    layout_button: Query<&Children, With<LayoutButton>>,
+    size_button: Query<&Children, With<SizeButton>>,
+) {
+    if !active.is_changed() {
+        return;
+    }
+
+    if let Ok(children) = layout_button.single() {
+        let mut iter = text_query.iter_many_mut(children);
+        while let Some(mut text) = iter.fetch_next() {
+            text.0 = kilter
+                .layouts
+                .get(&active.layout_id)
+                .map(|l| l.name.clone())
+                .unwrap_or_else(|| format!("Layout {}", active.layout_id));
+        }
+    }
+
+    if let Ok(children) = size_button.single() {
+        let mut iter = text_query.iter_many_mut(children);
+        while let Some(mut text) = iter.fetch_next() {
+            text.0 = active
+                .product_size_id
+                .and_then(|id| kilter.product_sizes.get(&id))
+                .map(|s| s.name.clone())
+                .unwrap_or_else(|| "Full".to_string());
+        }
+    }
+}
+
+/// Spawns a button for each set on the active layout when the layout changes.
+fn update_set_buttons(
+    mut commands: Commands,
+    active: Res<ActiveLayout>,
+    kilter: Res<KilterData>,
+    container: Query<Entity, With<SetButtons>>,
+    mut shown: Local<Vec<u32>>,
+) {
+    if !active.is_changed() && !kilter.is_changed() {
+        return;
+    }
+
+    let set_ids = kilter.layout_set_ids(active.layout_id);
+    if *shown == set_ids {
+        return;
+    }
+
+    let Ok(container) = container.single() else {
+        return;
+    };
+
+    commands.entity(container).despawn_related::<Children>();
+
+    // A single set can't be toggled off without hiding every climb.
+    if set_ids.len() > 1 {
+        for set_id in set_ids {
+            let button = button(&mut commands, "", SetButton(*set_id));
+            commands.entity(container).add_child(button);
+        }
+    }
+
+    *shown = set_ids.to_vec();
+}
+
+fn set_button(
+    query: Query<(&Interaction, &SetButton), Changed<Interaction>>,
+    mut active: ResMut<ActiveLayout>,
+) {
+    for (_, SetButton(set_id)) in query.iter().filter(|(i, _)| **i == Interaction::Pressed) {
+        if !active.missing_sets.remove(set_id) {
+            active.missing_sets.insert(*set_id);
+        }
+    }
+}
+
+fn set_buttons_text(
+    active: Res<ActiveLayout>,
+    kilter: Res<KilterData>,
+    buttons: Query<(&SetButton, &Children), Changed<Children>>,
+    all_buttons: Query<(&SetButton, &Children)>,
+    mut text_query: Query<(&mut Text, &mut TextColor)>,
+) {
+    let buttons: Vec<_> = if active.is_changed() {
+        all_buttons.iter().collect()
+    } else {
+        buttons.iter().collect()
+    };
+
+    for (SetButton(set_id), children) in buttons {
+        let installed = !active.missing_sets.contains(set_id);
+
+        let mut iter = text_query.iter_many_mut(children);
+        while let Some((mut text, mut color)) = iter.fetch_next() {
+            text.0 = kilter.set_name(*set_id);
+            color.0 = if installed {
+                theme::FONT_COLOR
+            } else {
+                theme::FONT_COLOR_MUTED
+            }
+            .into();
+        }
+    }
+}
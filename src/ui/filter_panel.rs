@@ -0,0 +1,305 @@
+use bevy::prelude::*;
+use bevy_simple_text_input::{
+    TextInput, TextInputInactive, TextInputPlaceholder, TextInputTextColor, TextInputTextFont,
+    TextInputValue,
+};
+
+use crate::kilter_board::{MAX_BOARD_ANGLE, MIN_BOARD_ANGLE};
+use crate::kilter_data::{ClimbQuery, HoldMatch, KilterData};
+
+use super::{button::button, theme};
+
+/// The filters chosen in the filter panel.
+///
+/// The angle and layout are filled in from the board when searching.
+#[derive(Resource, Default)]
+pub struct ClimbFilter(pub ClimbQuery);
+
+#[derive(Component)]
+struct FilterPanel;
+#[derive(Component)]
+struct FilterToggle;
+#[derive(Component)]
+struct FilterControls;
+#[derive(Component)]
+struct SetterField;
+#[derive(Component, Clone, Copy)]
+enum FilterButton {
+    Sort,
+    MinGrade,
+    MaxGrade,
+    Quality,
+    Ascents,
+    Benchmark,
+    Listed,
+    Draft,
+    Hidden,
+    SetterAngle,
+    Holds,
+}
+
+const QUALITIES: [f32; 3] = [1., 2., 2.5];
+const ASCENTS: [u32; 3] = [10, 100, 1000];
+
+pub struct FilterPanelPlugin;
+
+impl Plugin for FilterPanelPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ClimbFilter>()
+            .add_systems(Startup, setup_filter_panel)
+            .add_systems(
+                Update,
+                (
+                    toggle_filter_controls,
+                    filter_buttons,
+                    setter_field,
+                    filter_buttons_text,
+                ),
+            );
+    }
+}
+
+fn setup_filter_panel(mut commands: Commands) {
+    let panel = commands
+        .spawn((
+            Name::new("FilterPanel"),
+            FilterPanel,
+            Node {
+                flex_direction: FlexDirection::Column,
+                position_type: PositionType::Absolute,
+                top: Val::Px(60.),
+                right: Val::Px(212.),
+                width: Val::Px(200.),
+                padding: theme::CONTAINER_PADDING,
+                row_gap: Val::Px(5.),
+                ..default()
+            },
+            BorderRadius::all(theme::CONTAINER_BORDER_RADIUS),
+            BackgroundColor(theme::CONTAINER_BG.into()),
+        ))
+        .id();
+
+    let toggle = button(&mut commands, "Filters", FilterToggle);
+
+    let controls = commands
+        .spawn((
+            Node {
+                flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(5.),
+                display: Display::None,
+                ..default()
+            },
+            FilterControls,
+        ))
+        .id();
+
+    let buttons = [
+        FilterButton::Sort,
+        FilterButton::MinGrade,
+        FilterButton::MaxGrade,
+        FilterButton::Quality,
+        FilterButton::Ascents,
+        FilterButton::Benchmark,
+        FilterButton::Listed,
+        FilterButton::Draft,
+        FilterButton::Hidden,
+        FilterButton::SetterAngle,
+        FilterButton::Holds,
+    ]
+    .map(|marker| button(&mut commands, "", marker));
+
+    let setter_field = commands
+        .spawn((
+            Node::default(),
+            TextInput,
+            TextInputTextFont(TextFont {
+                font_size: theme::FONT_SIZE_SM,
+                ..default()
+            }),
+            TextInputTextColor(theme::FONT_COLOR.into()),
+            TextInputPlaceholder {
+                value: "Setter".to_string(),
+                ..default()
+            },
+            TextInputInactive(true),
+            SetterField,
+        ))
+        .id();
+
+    commands.entity(controls).add_children(&buttons);
+    commands.entity(controls).add_child(setter_field);
+    commands.entity(panel).add_children(&[toggle, controls]);
+}
+
+fn toggle_filter_controls(
+    query: Query<&Interaction, (With<FilterToggle>, Changed<Interaction>)>,
+    mut controls: Query<&mut Node, With<FilterControls>>,
+) {
+    if query.iter().any(|i| *i == Interaction::Pressed) {
+        if let Ok(mut node) = controls.single_mut() {
+            node.display = if node.display == Display::Flex {
+                Display::None
+            } else {
+                Display::Flex
+            }
+        }
+    }
+}
+
+/// Steps from `current` to the next of `values`, going back to `None` after
+/// the last.
+fn cycle<T: PartialEq + Copy>(current: Option<T>, values: &[T]) -> Option<T> {
+    match current.and_then(|c| values.iter().position(|v| *v == c)) {
+        Some(pos) => values.get(pos + 1).copied(),
+        None => values.first().copied(),
+    }
+}
+
+fn filter_buttons(
+    query: Query<(&Interaction, &FilterButton), Changed<Interaction>>,
+    mut filter: ResMut<ClimbFilter>,
+    kilter: Res<KilterData>,
+) {
+    for (interaction, button) in &query {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+
+        let query = &mut filter.0;
+
+        match button {
+            FilterButton::Sort => query.sort = query.sort.next(),
+            FilterButton::MinGrade | FilterButton::MaxGrade => {
+                let mut grades: Vec<_> = kilter
+                    .difficulty_grades
+                    .values()
+                    .filter(|g| g.is_listed)
+                    .map(|g| g.difficulty)
+                    .collect();
+                grades.sort();
+
+                if matches!(button, FilterButton::MinGrade) {
+                    query.min_difficulty = cycle(query.min_difficulty, &grades);
+                } else {
+                    query.max_difficulty = cycle(query.max_difficulty, &grades);
+                }
+            }
+            FilterButton::Quality => query.min_quality = cycle(query.min_quality, &QUALITIES),
+            FilterButton::Ascents => query.min_ascents = cycle(query.min_ascents, &ASCENTS),
+            FilterButton::Benchmark => query.benchmark_only = !query.benchmark_only,
+            FilterButton::Listed => query.is_listed = cycle(query.is_listed, &[true, false]),
+            FilterButton::Draft => query.is_draft = cycle(query.is_draft, &[true, false]),
+            FilterButton::Hidden => query.include_hidden = !query.include_hidden,
+            FilterButton::SetterAngle => {
+                let angles: Vec<_> = (MIN_BOARD_ANGLE..=MAX_BOARD_ANGLE).step_by(5).collect();
+                query.setter_angle = cycle(query.setter_angle, &angles);
+            }
+            FilterButton::Holds => {
+                // Loosen the search step by step, then stop searching by holds.
+                let has_roles = query.holds.iter().any(|h| h.role_id.is_some());
+                match (query.hold_match, has_roles) {
+                    (HoldMatch::All, true) => {
+                        for hold in &mut query.holds {
+                            hold.role_id = None;
+                        }
+                    }
+                    (HoldMatch::All, false) => query.hold_match = HoldMatch::Any,
+                    (HoldMatch::Any, _) => {
+                        query.holds.clear();
+                        query.hold_match = HoldMatch::All;
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn setter_field(
+    query: Query<&TextInputValue, (With<SetterField>, Changed<TextInputValue>)>,
+    mut filter: ResMut<ClimbFilter>,
+) {
+    let Ok(value) = query.single() else {
+        return;
+    };
+
+    let setter = value.0.trim();
+    filter.0.setter = (!setter.is_empty()).then(|| setter.to_string());
+}
+
+fn filter_buttons_text(
+    filter: Res<ClimbFilter>,
+    kilter: Res<KilterData>,
+    buttons: Query<(&FilterButton, &Children)>,
+    mut text_query: Query<&mut Text>,
+) {
+    if !filter.is_changed() {
+        return;
+    }
+
+    let query = &filter.0;
+
+    let grade = |difficulty: Option<u32>| {
+        difficulty
+            .map(|d| {
+                kilter
+                    .difficulty_grades
+                    .get(&d)
+                    .map(|g| g.boulder_name.clone())
+                    .unwrap_or_else(|| d.to_string())
+            })
+            .unwrap_or_else(|| "Any".to_string())
+    };
+
+    let yes_no = |value: Option<bool>| match value {
+        Some(true) => "Yes",
+        Some(false) => "No",
+        None => "Any",
+    };
+
+    for (button, children) in &buttons {
+        let label = match button {
+            FilterButton::Sort => format!("Sort: {}", query.sort.name()),
+            FilterButton::MinGrade => format!("Min Grade: {}", grade(query.min_difficulty)),
+            FilterButton::MaxGrade => format!("Max Grade: {}", grade(query.max_difficulty)),
+            FilterButton::Quality => match query.min_quality {
+                Some(q) => format!("Quality: {q}+"),
+                None => "Quality: Any".to_string(),
+            },
+            FilterButton::Ascents => match query.min_ascents {
+                Some(a) => format!("Ascents: {a}+"),
+                None => "Ascents: Any".to_string(),
+            },
+            FilterButton::Benchmark => format!(
+                "Benchmarks Only: {}",
+                if query.benchmark_only { "Yes" } else { "No" }
+            ),
+            FilterButton::Listed => format!("Listed: {}", yes_no(query.is_listed)),
+            FilterButton::Draft => format!("Draft: {}", yes_no(query.is_draft)),
+            FilterButton::Hidden if query.include_hidden => "Unlisted & Drafts: Shown".to_string(),
+            FilterButton::Hidden => "Unlisted & Drafts: Hidden".to_string(),
+            FilterButton::SetterAngle => match query.setter_angle {
+                Some(a) => format!("Setter Angle: {a}°"),
+                None => "Setter Angle: Any".to_string(),
+            },
+            FilterButton::Holds if query.holds.is_empty() => "Holds: Any".to_string(),
+            FilterButton::Holds => format!(
+                "Holds: {} of {}{}",
+                match query.hold_match {
+                    HoldMatch::All => "All",
+                    HoldMatch::Any => "Any",
+                },
+                query.holds.len(),
+                if query.holds.iter().any(|h| h.role_id.is_some()) {
+                    " w/ Roles"
+                } else {
+                    ""
+                }
+            ),
+        };
+
+        let mut iter = text_query.iter_many_mut(children);
+        while let Some(mut text) = iter.fetch_next() {
+            text.0.clone_from(&label);
+        }
+    }
+}
@@ -1,15 +1,36 @@
 use bevy::prelude::*;
+use bevy_simple_text_input::{
+    TextInput, TextInputInactive, TextInputPlaceholder, TextInputSettings, TextInputSubmitEvent,
+    TextInputTextColor, TextInputTextFont, TextInputValue,
+};
 
-use crate::{kilter_board::SelectedClimb, kilter_data::KilterData};
+use crate::{
+    history::{ClimbDetails, Edit, EditEvent},
+    kilter_board::{
+        ActiveLayout, BoardAngle, SelectedClimb, BOARD_ANGLE_STEP, MAX_BOARD_ANGLE, MIN_BOARD_ANGLE,
+    },
+    kilter_data::{Climb, KilterData},
+};
 
-use super::theme;
+use super::{button::button, theme, toast_panel::ToastEvent};
 
 pub struct InfoPanelPlugin;
 
 impl Plugin for InfoPanelPlugin {
     fn build(&self, app: &mut App) {
         app.add_systems(Startup, setup_info_panel);
-        app.add_systems(Update, (update_selected_climb, toggle_more_info));
+        app.add_systems(
+            Update,
+            (
+                update_selected_climb,
+                update_climb_issues,
+                toggle_more_info,
+                toggle_editor,
+                (sync_editor_fields, submit_editor_fields, blur_editor_fields).chain(),
+                editor_toggles,
+                editor_toggles_text,
+            ),
+        );
     }
 }
 
@@ -18,8 +39,14 @@ struct ClimbNameText;
 #[derive(Component)]
 struct ClimbAuthorText;
 #[derive(Component)]
+struct ClimbOriginText;
+#[derive(Component)]
 struct ClimbAngleText;
 #[derive(Component)]
+struct ClimbGradeText;
+#[derive(Component)]
+struct ClimbStatsText;
+#[derive(Component)]
 struct ClimbDescriptionText;
 #[derive(Component)]
 struct ClimbUuidText;
@@ -28,9 +55,27 @@ struct ClimbDraftText;
 #[derive(Component)]
 struct ClimbListedText;
 #[derive(Component)]
+struct ClimbIssuesText;
+#[derive(Component)]
 struct ClimbInfo;
 #[derive(Component)]
 struct ClimbMoreInfo;
+#[derive(Component)]
+struct EditorToggle;
+#[derive(Component)]
+struct ClimbEditor;
+#[derive(Component, Clone, Copy)]
+enum EditorField {
+    Name,
+    Description,
+    Setter,
+    Angle,
+}
+#[derive(Component, Clone, Copy)]
+enum EditorToggleButton {
+    Draft,
+    Listed,
+}
 
 fn setup_info_panel(mut commands: Commands) {
     let root = commands
@@ -42,6 +87,8 @@ fn setup_info_panel(mut commands: Commands) {
         })
         .id();
 
+    let mut more_info = Entity::PLACEHOLDER;
+
     let container = commands
         .spawn((
             Node {
@@ -83,9 +130,39 @@ fn setup_info_panel(mut commands: Commands) {
                         TextColor(theme::FONT_COLOR_MUTED.into()),
                         ClimbAuthorText,
                     ));
+
+                    parent.spawn((
+                        Text::new("".to_string()),
+                        TextFont {
+                            font_size: theme::FONT_SIZE,
+                            ..default()
+                        },
+                        TextColor(theme::FONT_COLOR_EMPHASIS.into()),
+                        ClimbGradeText,
+                    ));
+
+                    parent.spawn((
+                        Text::new("".to_string()),
+                        TextFont {
+                            font_size: theme::FONT_SIZE,
+                            ..default()
+                        },
+                        TextColor(theme::FONT_COLOR_MUTED.into()),
+                        ClimbOriginText,
+                    ));
                 });
 
-            parent
+            parent.spawn((
+                Text::new("".to_string()),
+                TextFont {
+                    font_size: theme::FONT_SIZE_SM,
+                    ..default()
+                },
+                TextColor(theme::FONT_COLOR_WARNING.into()),
+                ClimbIssuesText,
+            ));
+
+            more_info = parent
                 .spawn((
                     Node {
                         flex_direction: FlexDirection::Column,
@@ -114,6 +191,15 @@ fn setup_info_panel(mut commands: Commands) {
                         TextColor(theme::FONT_COLOR.into()),
                         ClimbAngleText,
                     ));
+                    parent.spawn((
+                        Text::new("No Stats".to_string()),
+                        TextFont {
+                            font_size: theme::FONT_SIZE,
+                            ..default()
+                        },
+                        TextColor(theme::FONT_COLOR.into()),
+                        ClimbStatsText,
+                    ));
                     parent.spawn((
                         Text::new("Draft:".to_string()),
                         TextFont {
@@ -121,6 +207,7 @@ fn setup_info_panel(mut commands: Commands) {
                             ..default()
                         },
                         TextColor(theme::FONT_COLOR.into()),
+                        ClimbDraftText,
                     ));
                     parent.spawn((
                         Text::new("Listed:".to_string()),
@@ -140,30 +227,108 @@ fn setup_info_panel(mut commands: Commands) {
                         TextColor(theme::FONT_COLOR.into()),
                         ClimbUuidText,
                     ));
-                });
+                })
+                .id();
+        })
+        .id();
+
+    let editor_toggle = button(&mut commands, "Edit", EditorToggle);
+
+    let editor = commands
+        .spawn((
+            Node {
+                flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(5.),
+                display: Display::None,
+                ..default()
+            },
+            ClimbEditor,
+        ))
+        .id();
+
+    for (field, label) in [
+        (EditorField::Name, "Name"),
+        (EditorField::Description, "Description"),
+        (EditorField::Setter, "Setter"),
+        (EditorField::Angle, "Setter Angle"),
+    ] {
+        let input = commands
+            .spawn((
+                Node {
+                    width: Val::Px(300.),
+                    ..default()
+                },
+                TextInput,
+                TextInputTextFont(TextFont {
+                    font_size: theme::FONT_SIZE_SM,
+                    ..default()
+                }),
+                TextInputTextColor(theme::FONT_COLOR.into()),
+                TextInputPlaceholder {
+                    value: label.to_string(),
+                    ..default()
+                },
+                TextInputSettings {
+                    retain_on_submit: true,
+                    ..default()
+                },
+                TextInputInactive(true),
+                field,
+            ))
+            .id();
+        commands.entity(editor).add_child(input);
+    }
+
+    let hint = commands
+        .spawn((
+            Text::new("Press Enter or click away to save a field"),
+            TextFont {
+                font_size: theme::FONT_SIZE_SM,
+                ..default()
+            },
+            TextColor(theme::FONT_COLOR_MUTED.into()),
+        ))
+        .id();
+
+    let toggles = commands
+        .spawn(Node {
+            column_gap: Val::Px(5.),
+            ..default()
         })
         .id();
+    let toggle_buttons = [EditorToggleButton::Draft, EditorToggleButton::Listed]
+        .map(|marker| button(&mut commands, "", marker));
+    commands.entity(toggles).add_children(&toggle_buttons);
+
+    commands.entity(editor).add_children(&[hint, toggles]);
 
+    commands
+        .entity(more_info)
+        .add_children(&[editor_toggle, editor]);
     commands.entity(root).add_child(container);
 }
 
 fn update_selected_climb(
     selected: Res<SelectedClimb>,
     kilter: Res<KilterData>,
+    angle: Res<BoardAngle>,
     mut text_query: Query<&mut Text>,
     climb_name_text_query: Query<Entity, With<ClimbNameText>>,
     climb_author_text_query: Query<Entity, With<ClimbAuthorText>>,
+    climb_origin_text_query: Query<Entity, With<ClimbOriginText>>,
     climb_angle_text_query: Query<Entity, With<ClimbAngleText>>,
+    climb_grade_text_query: Query<Entity, With<ClimbGradeText>>,
+    climb_stats_text_query: Query<Entity, With<ClimbStatsText>>,
     climb_description_text_query: Query<Entity, With<ClimbDescriptionText>>,
     climb_uuid_text_query: Query<Entity, With<ClimbUuidText>>,
     climb_draft_text_query: Query<Entity, With<ClimbDraftText>>,
     climb_listed_text_query: Query<Entity, With<ClimbListedText>>,
 ) {
     let Some(climb) = kilter
-        .climbs
+        .climbs()
         .iter()
         .nth(selected.0)
-        .or_else(|| kilter.climbs.iter().next())
+        .or_else(|| kilter.climbs().iter().next())
         .map(|(_, climb)| climb)
     else {
         return;
@@ -187,6 +352,18 @@ fn update_selected_climb(
         .0
         .clone_from(&format!("by {}", &climb.setter_username));
 
+    let Ok(origin_entity) = climb_origin_text_query.single() else {
+        return;
+    };
+    let Ok(mut origin_text) = text_query.get_mut(origin_entity) else {
+        return;
+    };
+    origin_text.0 = if climb.origin.is_local() {
+        format!("({})", climb.origin.name())
+    } else {
+        String::new()
+    };
+
     let Ok(angle_entity) = climb_angle_text_query.single() else {
         return;
     };
@@ -200,6 +377,41 @@ fn update_selected_climb(
             .unwrap_or_else(|| "Setter Angle: Unknown".to_string()),
     );
 
+    let stats = kilter.climb_stats(&climb.uuid, angle.0);
+
+    let Ok(grade_entity) = climb_grade_text_query.single() else {
+        return;
+    };
+    let Ok(mut grade_text) = text_query.get_mut(grade_entity) else {
+        return;
+    };
+    grade_text.0 = stats
+        .and_then(|stats| kilter.grade(stats.difficulty()))
+        .map(|grade| grade.boulder_name.clone())
+        .unwrap_or_default();
+
+    let Ok(stats_entity) = climb_stats_text_query.single() else {
+        return;
+    };
+    let Ok(mut stats_text) = text_query.get_mut(stats_entity) else {
+        return;
+    };
+    stats_text.0 = stats
+        .map(|stats| {
+            format!(
+                "At {}°: Quality {:.1}/3, {} Ascents{}",
+                angle.0,
+                stats.quality_average,
+                stats.ascensionist_count,
+                if stats.is_benchmark() {
+                    ", Benchmark"
+                } else {
+                    ""
+                }
+            )
+        })
+        .unwrap_or_else(|| format!("No Stats at {}°", angle.0));
+
     let Ok(description_entity) = climb_description_text_query.single() else {
         return;
     };
@@ -241,6 +453,46 @@ fn update_selected_climb(
         .clone_from(&format!("Listed: {:?}", climb.is_listed));
 }
 
+/// Lists the ways the selected climb breaks the board's rules, so they can be
+/// fixed while authoring, and any sets it uses that aren't installed.
+fn update_climb_issues(
+    selected: Res<SelectedClimb>,
+    kilter: Res<KilterData>,
+    active: Res<ActiveLayout>,
+    mut text_query: Query<&mut Text, With<ClimbIssuesText>>,
+) {
+    if !selected.is_changed() && !kilter.is_changed() && !active.is_changed() {
+        return;
+    }
+
+    let Ok(mut text) = text_query.single_mut() else {
+        return;
+    };
+
+    let Some((_, climb)) = kilter.climb_at(selected.0) else {
+        text.0.clear();
+        return;
+    };
+
+    let mut lines: Vec<_> = kilter
+        .validate_climb(climb, active.product_size_id)
+        .iter()
+        .map(ToString::to_string)
+        .collect();
+
+    let missing_sets: Vec<_> = kilter
+        .climb_set_ids(&climb.uuid)
+        .iter()
+        .filter(|id| active.missing_sets.contains(*id))
+        .map(|&id| kilter.set_name(id))
+        .collect();
+    if !missing_sets.is_empty() {
+        lines.push(format!("Uses missing sets: {}", missing_sets.join(", ")));
+    }
+
+    text.0 = lines.join("\n");
+}
+
 fn toggle_more_info(
     interaction_query: Query<&Interaction, (Changed<Interaction>, With<ClimbInfo>)>,
     mut more_info_query: Query<&mut Node, With<ClimbMoreInfo>>,
@@ -255,3 +507,226 @@ fn toggle_more_info(
         }
     }
 }
+
+fn toggle_editor(
+    query: Query<&Interaction, (With<EditorToggle>, Changed<Interaction>)>,
+    mut editor_query: Query<&mut Node, With<ClimbEditor>>,
+) {
+    if query.iter().any(|i| *i == Interaction::Pressed) {
+        if let Ok(mut node) = editor_query.single_mut() {
+            node.display = if node.display == Display::Flex {
+                Display::None
+            } else {
+                Display::Flex
+            }
+        }
+    }
+}
+
+/// Shows the selected climb's details in the editor, leaving any field that's
+/// being typed in alone unless a different climb was selected.
+fn sync_editor_fields(
+    selected: Res<SelectedClimb>,
+    kilter: Res<KilterData>,
+    mut fields: Query<(&EditorField, &mut TextInputValue, &TextInputInactive)>,
+) {
+    if !selected.is_changed() && !kilter.is_changed() {
+        return;
+    }
+
+    let Some((_, climb)) = kilter.climb_at(selected.0) else {
+        return;
+    };
+
+    for (field, mut value, inactive) in &mut fields {
+        if !selected.is_changed() && !inactive.0 {
+            continue;
+        }
+
+        let current = match field {
+            EditorField::Name => climb.name.clone(),
+            EditorField::Description => climb.description.clone(),
+            EditorField::Setter => climb.setter_username.clone(),
+            EditorField::Angle => climb.angle.map(|a| a.to_string()).unwrap_or_default(),
+        };
+
+        if value.0 != current {
+            value.0 = current;
+        }
+    }
+}
+
+fn submit_editor_fields(
+    mut events: EventReader<TextInputSubmitEvent>,
+    fields: Query<&EditorField>,
+    selected: Res<SelectedClimb>,
+    kilter: Res<KilterData>,
+    mut edits: EventWriter<EditEvent>,
+    mut toasts: EventWriter<ToastEvent>,
+) {
+    for event in events.read() {
+        let Ok(field) = fields.get(event.entity) else {
+            continue;
+        };
+
+        if let Some(edit) = edit_field(*field, &event.value, &kilter, &selected, &mut toasts) {
+            edits.write(EditEvent(edit));
+        }
+    }
+}
+
+/// Saves a field when it loses focus, like pressing Enter does.
+fn blur_editor_fields(
+    fields: Query<(&EditorField, &TextInputValue, Ref<TextInputInactive>)>,
+    selected: Res<SelectedClimb>,
+    kilter: Res<KilterData>,
+    mut edits: EventWriter<EditEvent>,
+    mut toasts: EventWriter<ToastEvent>,
+) {
+    for (field, value, inactive) in &fields {
+        if !inactive.is_changed() || inactive.is_added() || !inactive.0 {
+            continue;
+        }
+
+        if let Some(edit) = edit_field(*field, &value.0, &kilter, &selected, &mut toasts) {
+            edits.write(EditEvent(edit));
+        }
+    }
+}
+
+/// Builds an edit setting `field` of the selected climb to `value`, or `None`
+/// if nothing would change or the value is invalid.
+fn edit_field(
+    field: EditorField,
+    value: &str,
+    kilter: &KilterData,
+    selected: &SelectedClimb,
+    toasts: &mut EventWriter<ToastEvent>,
+) -> Option<Edit> {
+    let value = value.trim();
+
+    match field {
+        EditorField::Name => edit_details(kilter, selected, |c| c.name = value.to_string()),
+        EditorField::Description => {
+            edit_details(kilter, selected, |c| c.description = value.to_string())
+        }
+        EditorField::Setter => {
+            edit_details(kilter, selected, |c| c.setter_username = value.to_string())
+        }
+        EditorField::Angle => {
+            let angle = if value.is_empty() {
+                None
+            } else {
+                let Ok(angle) = value.trim_end_matches('°').parse() else {
+                    toasts.write(ToastEvent(format!("Invalid angle: {value}")));
+                    return None;
+                };
+
+                let (_, climb) = kilter.climb_at(selected.0)?;
+                let allowed = allowed_angles(kilter, climb.layout_id);
+                if !allowed.contains(&angle) {
+                    let allowed: Vec<_> = allowed.iter().map(|a| format!("{a}°")).collect();
+                    toasts.write(ToastEvent(format!(
+                        "Angle must be one of {}",
+                        allowed.join(", ")
+                    )));
+                    return None;
+                }
+
+                Some(angle)
+            };
+            edit_details(kilter, selected, |c| c.angle = angle)
+        }
+    }
+}
+
+/// The angles a climb on `layout_id` can be set at, or every angle the board
+/// tilts to if the layout's aren't known.
+fn allowed_angles(kilter: &KilterData, layout_id: u32) -> Vec<u32> {
+    match kilter.layout_angles(layout_id) {
+        Some(angles) => angles.iter().copied().collect(),
+        None => (MIN_BOARD_ANGLE..=MAX_BOARD_ANGLE)
+            .step_by(BOARD_ANGLE_STEP as usize)
+            .collect(),
+    }
+}
+
+fn editor_toggles(
+    query: Query<(&Interaction, &EditorToggleButton), Changed<Interaction>>,
+    selected: Res<SelectedClimb>,
+    kilter: Res<KilterData>,
+    mut edits: EventWriter<EditEvent>,
+) {
+    for (interaction, button) in &query {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+
+        let edit = match button {
+            EditorToggleButton::Draft => edit_details(&kilter, &selected, |c| {
+                c.is_draft = !c.is_draft;
+            }),
+            EditorToggleButton::Listed => edit_details(&kilter, &selected, |c| {
+                c.is_listed = !c.is_listed;
+            }),
+        };
+
+        if let Some(edit) = edit {
+            edits.write(EditEvent(edit));
+        }
+    }
+}
+
+fn editor_toggles_text(
+    selected: Res<SelectedClimb>,
+    kilter: Res<KilterData>,
+    buttons: Query<(&EditorToggleButton, &Children)>,
+    mut text_query: Query<&mut Text>,
+) {
+    if !selected.is_changed() && !kilter.is_changed() {
+        return;
+    }
+
+    let Some((_, climb)) = kilter.climb_at(selected.0) else {
+        return;
+    };
+
+    let yes_no = |value: bool| if value { "Yes" } else { "No" };
+
+    for (button, children) in &buttons {
+        let label = match button {
+            EditorToggleButton::Draft => format!("Draft: {}", yes_no(climb.is_draft)),
+            EditorToggleButton::Listed => format!("Listed: {}", yes_no(climb.is_listed)),
+        };
+
+        let mut iter = text_query.iter_many_mut(children);
+        while let Some(mut text) = iter.fetch_next() {
+            text.0.clone_from(&label);
+        }
+    }
+}
+
+/// Builds an edit changing the selected climb's details, or `None` if nothing
+/// would change.
+fn edit_details(
+    kilter: &KilterData,
+    selected: &SelectedClimb,
+    change: impl FnOnce(&mut Climb),
+) -> Option<Edit> {
+    let (_, climb) = kilter.climb_at(selected.0)?;
+
+    let mut changed = climb.clone();
+    change(&mut changed);
+
+    let (before, after) = ClimbDetails::diff(climb, &changed);
+    if after.is_empty() {
+        return None;
+    }
+
+    Some(Edit::Details {
+        uuid: climb.uuid.clone(),
+        before,
+        after,
+        origin: climb.origin,
+    })
+}
@@ -0,0 +1,45 @@
+use bevy::prelude::*;
+
+use crate::data_loading::DataState;
+
+use super::theme;
+
+pub struct LoadingPanelPlugin;
+
+impl Plugin for LoadingPanelPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(DataState::Loading), setup_loading_panel);
+    }
+}
+
+/// Covers the app, keeping it from being used, until climb data is ready.
+fn setup_loading_panel(mut commands: Commands) {
+    let container = commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.),
+                height: Val::Percent(100.),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            BackgroundColor(theme::CONTAINER_BG.into()),
+            GlobalZIndex(i32::MAX),
+            StateScoped(DataState::Loading),
+        ))
+        .id();
+
+    let label = commands
+        .spawn((
+            Text::new("Loading climbs..."),
+            TextFont {
+                font_size: theme::FONT_SIZE,
+                ..default()
+            },
+            TextColor(theme::FONT_COLOR.into()),
+        ))
+        .id();
+
+    commands.entity(container).add_child(label);
+}
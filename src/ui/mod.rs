@@ -2,19 +2,28 @@ use bevy::prelude::*;
 
 use self::{
     action_panel::ActionPanelPlugin, board_panel::BoardPanelPlugin, button::ButtonPlugin,
-    font::FontPlugin, info_panel::InfoPanelPlugin, nav_panel::NavPanelPlugin,
-    net_panel::NetPanelPlugin, search_panel::SearchPanelPlugin,
+    filter_panel::FilterPanelPlugin, font::FontPlugin, info_panel::InfoPanelPlugin,
+    loading_panel::LoadingPanelPlugin, nav_panel::NavPanelPlugin, net_panel::NetPanelPlugin,
+    playback_panel::PlaybackPanelPlugin, role_menu::RoleMenuPlugin,
+    search_panel::SearchPanelPlugin, text_input::TextInputFocusPlugin,
+    toast_panel::ToastPanelPlugin,
 };
 
 mod action_panel;
 mod board_panel;
 mod button;
+mod filter_panel;
 mod font;
 mod info_panel;
+mod loading_panel;
 mod nav_panel;
 mod net_panel;
+mod playback_panel;
+pub mod role_menu;
 mod search_panel;
+mod text_input;
 mod theme;
+pub mod toast_panel;
 
 pub struct UiPlugin;
 
@@ -27,8 +36,14 @@ impl Plugin for UiPlugin {
             ActionPanelPlugin,
             NavPanelPlugin,
             NetPanelPlugin,
+            PlaybackPanelPlugin,
             FontPlugin,
             SearchPanelPlugin,
+            FilterPanelPlugin,
+            TextInputFocusPlugin,
+            ToastPanelPlugin,
+            RoleMenuPlugin,
+            LoadingPanelPlugin,
         ));
     }
 }
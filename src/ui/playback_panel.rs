@@ -0,0 +1,158 @@
+use bevy::prelude::*;
+
+use crate::{
+    kilter_board::{FramePlayback, SelectedClimb},
+    kilter_data::KilterData,
+};
+
+use super::{button::button, theme};
+
+#[derive(Component)]
+struct PlaybackPanel;
+#[derive(Component)]
+struct StepBackButton;
+#[derive(Component)]
+struct PlayPauseButton;
+#[derive(Component)]
+struct StepForwardButton;
+#[derive(Component)]
+struct FrameText;
+
+pub struct PlaybackPanelPlugin;
+
+impl Plugin for PlaybackPanelPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, setup_playback_panel);
+        app.add_systems(
+            Update,
+            (
+                step_back_button,
+                play_pause_button,
+                step_forward_button,
+                update_playback_panel,
+            ),
+        );
+    }
+}
+
+fn setup_playback_panel(mut commands: Commands) {
+    let container = commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                top: Val::Px(60.),
+                left: Val::Px(0.),
+                column_gap: Val::Px(12.),
+                align_items: AlignItems::Center,
+                padding: theme::CONTAINER_PADDING,
+                display: Display::None,
+                ..default()
+            },
+            BorderRadius::right(theme::CONTAINER_BORDER_RADIUS),
+            BackgroundColor(theme::CONTAINER_BG.into()),
+            PlaybackPanel,
+        ))
+        .id();
+
+    let frame_text = commands
+        .spawn((
+            Text::new("1/1"),
+            TextFont {
+                font_size: theme::FONT_SIZE,
+                ..default()
+            },
+            TextColor(theme::FONT_COLOR.into()),
+            FrameText,
+        ))
+        .id();
+
+    let step_back_button = button(&mut commands, "<", StepBackButton);
+    let play_pause_button = button(&mut commands, "Play", PlayPauseButton);
+    let step_forward_button = button(&mut commands, ">", StepForwardButton);
+
+    commands.entity(container).add_children(&[
+        step_back_button,
+        play_pause_button,
+        step_forward_button,
+        frame_text,
+    ]);
+}
+
+fn selected_frames_count(selected: &SelectedClimb, kilter: &KilterData) -> usize {
+    kilter
+        .climb_at(selected.0)
+        .map(|(_, climb)| climb.frames.len())
+        .unwrap_or(0)
+}
+
+fn step_back_button(
+    query: Query<&Interaction, (With<StepBackButton>, Changed<Interaction>)>,
+    mut playback: ResMut<FramePlayback>,
+    selected: Res<SelectedClimb>,
+    kilter: Res<KilterData>,
+) {
+    if query.iter().any(|i| *i == Interaction::Pressed) {
+        playback.playing = false;
+        playback.step(-1, selected_frames_count(&selected, &kilter));
+    }
+}
+
+fn play_pause_button(
+    query: Query<&Interaction, (With<PlayPauseButton>, Changed<Interaction>)>,
+    mut playback: ResMut<FramePlayback>,
+) {
+    if query.iter().any(|i| *i == Interaction::Pressed) {
+        playback.playing = !playback.playing;
+    }
+}
+
+fn step_forward_button(
+    query: Query<&Interaction, (With<StepForwardButton>, Changed<Interaction>)>,
+    mut playback: ResMut<FramePlayback>,
+    selected: Res<SelectedClimb>,
+    kilter: Res<KilterData>,
+) {
+    if query.iter().any(|i| *i == Interaction::Pressed) {
+        playback.playing = false;
+        playback.step(1, selected_frames_count(&selected, &kilter));
+    }
+}
+
+fn update_playback_panel(
+    playback: Res<FramePlayback>,
+    selected: Res<SelectedClimb>,
+    kilter: Res<KilterData>,
+    mut panel_query: Query<&mut Node, With<PlaybackPanel>>,
+    mut text_query: Query<&mut Text>,
+    frame_text_query: Query<Entity, With<FrameText>>,
+    play_pause_button: Query<&Children, With<PlayPauseButton>>,
+) {
+    if !playback.is_changed() && !selected.is_changed() {
+        return;
+    }
+
+    let frames_count = selected_frames_count(&selected, &kilter);
+
+    // Only multi-frame climbs need playback controls.
+    if let Ok(mut node) = panel_query.single_mut() {
+        node.display = if frames_count > 1 {
+            Display::Flex
+        } else {
+            Display::None
+        };
+    }
+
+    if let Ok(entity) = frame_text_query.single() {
+        if let Ok(mut text) = text_query.get_mut(entity) {
+            text.0 = format!("{}/{}", playback.frame + 1, frames_count);
+        }
+    }
+
+    let Ok(children) = play_pause_button.single() else {
+        return;
+    };
+    let mut iter = text_query.iter_many_mut(children);
+    while let Some(mut text) = iter.fetch_next() {
+        text.0 = if playback.playing { "Pause" } else { "Play" }.to_string();
+    }
+}
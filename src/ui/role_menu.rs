@@ -0,0 +1,170 @@
+use bevy::prelude::*;
+
+use crate::{
+    authoring::set_hold_edit,
+    history::EditEvent,
+    kilter_board::{ActiveLayout, FramePlayback, SelectedClimb},
+    kilter_data::KilterData,
+};
+
+use super::theme;
+
+/// Opens a ring of buttons around `position`, in window coordinates, for
+/// choosing a placement's role.
+#[derive(Event)]
+pub struct OpenRoleMenu {
+    pub placement_id: u32,
+    pub position: Vec2,
+}
+
+#[derive(Component)]
+struct RoleMenu(u32);
+/// Gives the menu's placement a role, or removes it if `None`.
+#[derive(Component)]
+struct RoleMenuItem(Option<u32>);
+
+const MENU_RADIUS: f32 = 60.;
+const ITEM_SIZE: f32 = 56.;
+
+pub struct RoleMenuPlugin;
+
+impl Plugin for RoleMenuPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<OpenRoleMenu>().add_systems(
+            Update,
+            (open_role_menu, role_menu_items, close_role_menu).chain(),
+        );
+    }
+}
+
+fn open_role_menu(
+    mut commands: Commands,
+    mut events: EventReader<OpenRoleMenu>,
+    menus: Query<Entity, With<RoleMenu>>,
+    kilter: Res<KilterData>,
+    active: Res<ActiveLayout>,
+) {
+    let Some(event) = events.read().last() else {
+        return;
+    };
+
+    for entity in &menus {
+        commands.entity(entity).despawn();
+    }
+
+    let roles = kilter.sorted_placement_roles(active.product_id(&kilter).unwrap_or(1));
+
+    let mut items: Vec<(Option<u32>, String, Color)> = roles
+        .iter()
+        .map(|role| {
+            (
+                Some(role.id),
+                role.full_name.clone(),
+                Srgba::hex(&role.screen_color)
+                    .unwrap_or(theme::NORMAL_BUTTON)
+                    .into(),
+            )
+        })
+        .collect();
+    items.push((None, "Remove".to_string(), theme::CONTAINER_BG.into()));
+
+    let menu = commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                left: Val::Px(event.position.x),
+                top: Val::Px(event.position.y),
+                ..default()
+            },
+            RoleMenu(event.placement_id),
+        ))
+        .id();
+
+    for (i, (role_id, name, color)) in items.into_iter().enumerate() {
+        // Start at the top and go clockwise.
+        let angle = i as f32 / (roles.len() + 1) as f32 * std::f32::consts::TAU;
+        let offset = Vec2::new(angle.sin(), -angle.cos()) * MENU_RADIUS - ITEM_SIZE / 2.;
+
+        let item = commands
+            .spawn((
+                Node {
+                    position_type: PositionType::Absolute,
+                    left: Val::Px(offset.x),
+                    top: Val::Px(offset.y),
+                    width: Val::Px(ITEM_SIZE),
+                    height: Val::Px(ITEM_SIZE),
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    border: UiRect::all(Val::Px(2.)),
+                    ..default()
+                },
+                BorderRadius::MAX,
+                BorderColor(Color::BLACK),
+                BackgroundColor(color),
+                Interaction::None,
+                RoleMenuItem(role_id),
+            ))
+            .with_children(|parent| {
+                parent.spawn((
+                    Text::new(name),
+                    TextFont {
+                        font_size: theme::FONT_SIZE_SM,
+                        ..default()
+                    },
+                    TextColor(Color::BLACK),
+                    TextLayout::new_with_justify(JustifyText::Center),
+                ));
+            })
+            .id();
+
+        commands.entity(menu).add_child(item);
+    }
+}
+
+fn role_menu_items(
+    mut commands: Commands,
+    mut items: Query<(&Interaction, &RoleMenuItem, &mut BorderColor), Changed<Interaction>>,
+    menus: Query<(Entity, &RoleMenu)>,
+    kilter: Res<KilterData>,
+    selected: Res<SelectedClimb>,
+    playback: Res<FramePlayback>,
+    mut edits: EventWriter<EditEvent>,
+) {
+    for (interaction, item, mut border) in &mut items {
+        match interaction {
+            Interaction::Pressed => {
+                for (entity, menu) in &menus {
+                    if let Some(edit) = set_hold_edit(&kilter, &selected, &playback, menu.0, item.0)
+                    {
+                        edits.write(EditEvent(edit));
+                    }
+                    commands.entity(entity).despawn();
+                }
+            }
+            Interaction::Hovered => border.0 = Color::WHITE,
+            Interaction::None => border.0 = Color::BLACK,
+        }
+    }
+}
+
+/// Closes the menu when pressing anywhere else or Escape.
+fn close_role_menu(
+    mut commands: Commands,
+    mouse_input: Res<ButtonInput<MouseButton>>,
+    touches: Res<Touches>,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    items: Query<&Interaction, With<RoleMenuItem>>,
+    menus: Query<Entity, With<RoleMenu>>,
+) {
+    let pressed_elsewhere = (mouse_input.get_just_pressed().next().is_some()
+        || touches.any_just_pressed())
+        && items.iter().all(|i| *i == Interaction::None);
+
+    if !pressed_elsewhere && !keyboard_input.just_pressed(KeyCode::Escape) {
+        return;
+    }
+
+    for entity in &menus {
+        commands.entity(entity).despawn();
+    }
+}
@@ -1,26 +1,48 @@
-use bevy::prelude::*;
-use bevy_simple_text_input::{TextInput, TextInputTextColor, TextInputTextFont, TextInputValue};
+use bevy::{
+    input::mouse::{MouseScrollUnit, MouseWheel},
+    picking::hover::HoverMap,
+    prelude::*,
+};
+use bevy_simple_text_input::{
+    TextInput, TextInputInactive, TextInputTextColor, TextInputTextFont, TextInputValue,
+};
 
 use super::theme;
 
-use crate::kilter_board::ChangeClimbEvent;
-use crate::kilter_data::KilterData;
+use crate::data_loading::DataState;
+use crate::kilter_board::{ActiveLayout, BoardAngle, ChangeClimbEvent};
+use crate::kilter_data::{ClimbQuery, KilterData};
+
+use super::filter_panel::ClimbFilter;
 
 #[derive(Component)]
 struct SearchField;
 #[derive(Component)]
 struct SearchResultsPanel;
+/// A result, by climb uuid so that it still selects the same climb after
+/// others are removed.
 #[derive(Component)]
-struct SearchResultItem(usize);
+struct SearchResultItem(String);
 #[derive(Component)]
 struct SearchPanel;
 
+const MAX_RESULTS: usize = 100;
+const RESULTS_MAX_HEIGHT: f32 = 400.;
+const SCROLL_LINE_HEIGHT: f32 = 21.;
+
 pub struct SearchPanelPlugin;
 
 impl Plugin for SearchPanelPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Startup, setup_search_ui)
-            .add_systems(Update, (update_search_results, handle_search_result_click));
+        app.add_systems(Startup, setup_search_ui).add_systems(
+            Update,
+            (
+                update_search_results,
+                handle_search_result_click,
+                scroll_search_results,
+            )
+                .run_if(in_state(DataState::Ready)),
+        );
     }
 }
 
@@ -51,23 +73,30 @@ fn setup_search_ui(mut commands: Commands) {
                     ..default()
                 }),
                 TextInputTextColor(theme::FONT_COLOR.into()),
+                TextInputInactive(true),
                 SearchField,
             ));
             parent.spawn((
                 Node {
                     flex_direction: FlexDirection::Column,
                     row_gap: Val::Px(5.),
+                    max_height: Val::Px(RESULTS_MAX_HEIGHT),
+                    overflow: Overflow::scroll_y(),
                     ..default()
                 },
+                ScrollPosition::default(),
                 SearchResultsPanel,
             ));
         });
 }
 
 fn update_search_results(
-    search_field: Query<&TextInputValue, (With<SearchField>, Changed<TextInputValue>)>,
+    search_field: Query<Ref<TextInputValue>, With<SearchField>>,
     kilter: Res<KilterData>,
-    results_panel: Query<Entity, With<SearchResultsPanel>>,
+    active: Res<ActiveLayout>,
+    angle: Res<BoardAngle>,
+    filter: Res<ClimbFilter>,
+    mut results_panel: Query<(Entity, &mut ScrollPosition), With<SearchResultsPanel>>,
     mut search_panel: Query<&mut Node, With<SearchPanel>>,
     mut commands: Commands,
 ) {
@@ -75,7 +104,18 @@ fn update_search_results(
         return;
     };
 
-    let Ok(panel_entity) = results_panel.single() else {
+    let query_changed = search_text.is_changed()
+        || filter.is_changed()
+        || active.is_changed()
+        || angle.is_changed();
+
+    // Climbs can be added, changed or removed while searching, e.g. by syncing
+    // or pasting.
+    if !query_changed && !kilter.is_changed() {
+        return;
+    }
+
+    let Ok((panel_entity, mut scroll)) = results_panel.single_mut() else {
         return;
     };
 
@@ -83,7 +123,9 @@ fn update_search_results(
         return;
     };
 
-    if search_text.0.is_empty() {
+    let filtering = filter.0 != ClimbQuery::default();
+
+    if search_text.0.is_empty() && !filtering {
         panel_node.display = Display::None;
         return;
     }
@@ -92,13 +134,25 @@ fn update_search_results(
 
     // Despawn existing search result entities
     commands.entity(panel_entity).despawn_related::<Children>();
+    if query_changed {
+        scroll.offset_y = 0.;
+    }
+
+    let query = ClimbQuery {
+        name: search_text.0.clone(),
+        angle: angle.0,
+        layout_id: Some(active.layout_id),
+        product_size_id: active.product_size_id,
+        excluded_sets: active.missing_sets.clone(),
+        ..filter.0.clone()
+    };
 
-    let results = kilter.search_by_name(&search_text.0);
+    let results: Vec<_> = kilter.query(&query).into_iter().take(MAX_RESULTS).collect();
     if results.is_empty() {
         return;
     }
 
-    for (climb_idx, climb) in results.iter().take(10) {
+    for (climb_idx, climb) in results.iter() {
         let result = commands
             .spawn((
                 Button,
@@ -108,11 +162,15 @@ fn update_search_results(
                     ..default()
                 },
                 BackgroundColor(theme::CONTAINER_BG.into()),
-                SearchResultItem(*climb_idx),
+                SearchResultItem(climb.uuid.clone()),
             ))
             .with_children(|parent| {
                 parent.spawn((
-                    Text::new(format!("{}: {}", climb_idx, climb.name)),
+                    Text::new(if climb.origin.is_local() {
+                        format!("{}: {} ({})", climb_idx, climb.name, climb.origin.name())
+                    } else {
+                        format!("{}: {}", climb_idx, climb.name)
+                    }),
                     TextFont {
                         font_size: theme::FONT_SIZE_SM,
                         ..default()
@@ -128,11 +186,52 @@ fn update_search_results(
 
 fn handle_search_result_click(
     query: Query<(&Interaction, &SearchResultItem), (Changed<Interaction>, With<SearchResultItem>)>,
+    kilter: Res<KilterData>,
     mut writer: EventWriter<ChangeClimbEvent>,
 ) {
     for (interaction, item) in &query {
-        if *interaction == Interaction::Pressed {
-            writer.write(ChangeClimbEvent::SelectByIndex(item.0));
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+
+        if let Some(idx) = kilter.climb_index(&item.0) {
+            writer.write(ChangeClimbEvent::SelectByIndex(idx));
         }
     }
 }
+
+fn scroll_search_results(
+    mut wheel_events: EventReader<MouseWheel>,
+    hover_map: Res<HoverMap>,
+    parents: Query<&ChildOf>,
+    mut results_panel: Query<(Entity, &mut ScrollPosition), With<SearchResultsPanel>>,
+) {
+    let Ok((panel_entity, mut scroll)) = results_panel.single_mut() else {
+        return;
+    };
+
+    let dy = wheel_events
+        .read()
+        .map(|ev| match ev.unit {
+            MouseScrollUnit::Line => ev.y * SCROLL_LINE_HEIGHT,
+            MouseScrollUnit::Pixel => ev.y,
+        })
+        .sum::<f32>();
+
+    if dy == 0. {
+        return;
+    }
+
+    // The hovered entity is usually a result's text, so look for the panel
+    // among its ancestors.
+    let hovered = hover_map
+        .values()
+        .flat_map(|hits| hits.keys())
+        .any(|entity| {
+            *entity == panel_entity || parents.iter_ancestors(*entity).any(|e| e == panel_entity)
+        });
+
+    if hovered {
+        scroll.offset_y = (scroll.offset_y - dy).max(0.);
+    }
+}
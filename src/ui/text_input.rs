@@ -0,0 +1,50 @@
+use bevy::{
+    picking::events::{Click, Pointer},
+    prelude::*,
+};
+use bevy_simple_text_input::{TextInput, TextInputInactive};
+
+/// Sends keystrokes to the most recently clicked text input only. Clicking
+/// outside the UI, e.g. on the board, leaves no text input focused.
+///
+/// Text inputs that shouldn't have focus initially should be spawned with
+/// `TextInputInactive(true)`.
+pub struct TextInputFocusPlugin;
+
+impl Plugin for TextInputFocusPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, (focus, unfocus));
+    }
+}
+
+fn focus(
+    clicked: Query<(Entity, &Interaction), (Changed<Interaction>, With<TextInput>)>,
+    mut inputs: Query<(Entity, &mut TextInputInactive)>,
+) {
+    let Some((clicked, _)) = clicked.iter().find(|(_, i)| **i == Interaction::Pressed) else {
+        return;
+    };
+
+    for (entity, mut inactive) in &mut inputs {
+        let should_be_inactive = entity != clicked;
+        if inactive.0 != should_be_inactive {
+            inactive.0 = should_be_inactive;
+        }
+    }
+}
+
+fn unfocus(
+    mut click_events: EventReader<Pointer<Click>>,
+    ui_nodes: Query<(), With<ComputedNode>>,
+    mut inputs: Query<&mut TextInputInactive>,
+) {
+    if !click_events.read().any(|e| !ui_nodes.contains(e.target)) {
+        return;
+    }
+
+    for mut inactive in &mut inputs {
+        if !inactive.0 {
+            inactive.0 = true;
+        }
+    }
+}
@@ -5,6 +5,7 @@ pub const FONT_SIZE_SM: f32 = 14.0;
 
 pub const FONT_COLOR: Srgba = tailwind::VIOLET_50;
 pub const FONT_COLOR_EMPHASIS: Srgba = tailwind::VIOLET_300;
+pub const FONT_COLOR_WARNING: Srgba = tailwind::AMBER_300;
 pub const FONT_COLOR_MUTED: Srgba =
     Srgba::new(FONT_COLOR.red, FONT_COLOR.green, FONT_COLOR.blue, 0.6);
 
@@ -0,0 +1,104 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+
+use super::theme;
+
+/// Shows a short message to the user for a few seconds.
+#[derive(Event)]
+pub struct ToastEvent(pub String);
+
+#[derive(Component)]
+struct ToastPanel;
+#[derive(Component)]
+struct Toast(Timer);
+
+const TOAST_DURATION: Duration = Duration::from_secs(5);
+const MAX_TOASTS: usize = 4;
+
+pub struct ToastPanelPlugin;
+
+impl Plugin for ToastPanelPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<ToastEvent>()
+            .add_systems(Startup, setup_toast_panel)
+            .add_systems(Update, (spawn_toasts, expire_toasts));
+    }
+}
+
+fn setup_toast_panel(mut commands: Commands) {
+    commands
+        .spawn(Node {
+            position_type: PositionType::Absolute,
+            bottom: Val::Px(70.),
+            left: Val::Px(0.),
+            width: Val::Percent(100.),
+            justify_content: JustifyContent::Center,
+            ..default()
+        })
+        .with_children(|parent| {
+            parent.spawn((
+                Node {
+                    flex_direction: FlexDirection::Column,
+                    row_gap: Val::Px(5.),
+                    max_width: Val::Px(600.),
+                    ..default()
+                },
+                ToastPanel,
+            ));
+        });
+}
+
+fn spawn_toasts(
+    mut commands: Commands,
+    mut events: EventReader<ToastEvent>,
+    panel: Query<(Entity, Option<&Children>), With<ToastPanel>>,
+) {
+    let Ok((panel, children)) = panel.single() else {
+        return;
+    };
+
+    let messages: Vec<_> = events.read().map(|e| e.0.clone()).collect();
+
+    // Drop the oldest toasts rather than covering the board.
+    if let Some(children) = children {
+        let excess = (children.len() + messages.len()).saturating_sub(MAX_TOASTS);
+        for entity in &children[..excess.min(children.len())] {
+            commands.entity(*entity).despawn();
+        }
+    }
+
+    for message in messages {
+        let toast = commands
+            .spawn((
+                Node {
+                    padding: theme::CONTAINER_PADDING,
+                    ..default()
+                },
+                BorderRadius::all(theme::CONTAINER_BORDER_RADIUS),
+                BackgroundColor(theme::CONTAINER_BG.into()),
+                Toast(Timer::new(TOAST_DURATION, TimerMode::Once)),
+            ))
+            .with_children(|parent| {
+                parent.spawn((
+                    Text::new(message),
+                    TextFont {
+                        font_size: theme::FONT_SIZE_SM,
+                        ..default()
+                    },
+                    TextColor(theme::FONT_COLOR.into()),
+                ));
+            })
+            .id();
+
+        commands.entity(panel).add_child(toast);
+    }
+}
+
+fn expire_toasts(mut commands: Commands, mut query: Query<(Entity, &mut Toast)>, time: Res<Time>) {
+    for (entity, mut toast) in &mut query {
+        if toast.0.tick(time.delta()).finished() {
+            commands.entity(entity).despawn();
+        }
+    }
+}
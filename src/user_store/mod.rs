@@ -0,0 +1,266 @@
+use std::fmt::{self, Display};
+
+use bevy::{
+    platform::collections::{HashMap, HashSet},
+    prelude::*,
+};
+use serde_derive::{Deserialize, Serialize};
+
+use crate::{
+    data_loading::DataState,
+    kilter_board::ActiveLayout,
+    kilter_data::{Climb, ClimbOrigin, KilterData},
+    ui::toast_panel::ToastEvent,
+};
+
+#[cfg(not(any(target_arch = "wasm32", target_os = "android", target_os = "ios")))]
+mod native_store;
+#[cfg(target_arch = "wasm32")]
+mod wasm_store;
+
+#[cfg(not(any(target_arch = "wasm32", target_os = "android", target_os = "ios")))]
+use native_store as backend;
+#[cfg(target_arch = "wasm32")]
+use wasm_store as backend;
+
+// There's no user store on mobile yet.
+#[cfg(any(target_os = "android", target_os = "ios"))]
+mod backend {
+    use super::{StoredClimb, UserStoreError};
+
+    pub fn load() -> Result<Vec<Result<StoredClimb, UserStoreError>>, UserStoreError> {
+        Ok(vec![])
+    }
+
+    pub fn save(_changed: &[StoredClimb], _removed: &[String]) -> Result<(), UserStoreError> {
+        Ok(())
+    }
+
+    pub fn load_missing_sets() -> Result<Option<Vec<u32>>, UserStoreError> {
+        Ok(None)
+    }
+
+    pub fn save_missing_sets(_set_ids: &[u32]) -> Result<(), UserStoreError> {
+        Ok(())
+    }
+}
+
+/// Saves climbs that didn't come from the database, so that authored, pasted,
+/// generated and edited climbs survive restarts, along with which sets aren't
+/// installed.
+pub struct UserStorePlugin;
+
+impl Plugin for UserStorePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SavedClimbs>()
+            .init_resource::<SavedMissingSets>()
+            .add_systems(Startup, load_missing_sets)
+            .add_systems(OnEnter(DataState::Ready), load_user_climbs)
+            .add_systems(
+                Update,
+                (
+                    save_user_climbs.run_if(in_state(DataState::Ready)),
+                    save_missing_sets,
+                ),
+            );
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct StoredClimb {
+    origin: ClimbOrigin,
+    climb: Climb,
+}
+
+/// What the store holds, as of the last load or save.
+#[derive(Resource, Default)]
+struct SavedClimbs {
+    /// Whether the store has been read. Until then, saving could overwrite
+    /// climbs that haven't been loaded.
+    loaded: bool,
+    /// Each saved climb serialized, by uuid, so that only the climbs that
+    /// changed are written.
+    climbs: HashMap<String, String>,
+    /// The [`KilterData::climbs_generation`] saved up to. Only climbs changed
+    /// since are checked.
+    generation: u64,
+}
+
+/// The sets that aren't installed, as of the last load or save.
+#[derive(Resource, Default)]
+struct SavedMissingSets {
+    /// Whether the store has been read, like [`SavedClimbs::loaded`].
+    loaded: bool,
+    set_ids: HashSet<u32>,
+}
+
+#[derive(Debug)]
+pub enum UserStoreError {
+    #[cfg(not(any(target_arch = "wasm32", target_os = "android", target_os = "ios")))]
+    Sqlite(rusqlite::Error),
+    Json(serde_json::Error),
+    /// The browser's storage is unavailable or refused the write.
+    #[cfg(target_arch = "wasm32")]
+    Storage(String),
+}
+impl Display for UserStoreError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            #[cfg(not(any(target_arch = "wasm32", target_os = "android", target_os = "ios")))]
+            Self::Sqlite(e) => write!(f, "SQLite error: {e}"),
+            Self::Json(e) => write!(f, "JSON error: {e}"),
+            #[cfg(target_arch = "wasm32")]
+            Self::Storage(e) => write!(f, "Storage error: {e}"),
+        }
+    }
+}
+impl std::error::Error for UserStoreError {}
+#[cfg(not(any(target_arch = "wasm32", target_os = "android", target_os = "ios")))]
+impl From<rusqlite::Error> for UserStoreError {
+    fn from(value: rusqlite::Error) -> Self {
+        Self::Sqlite(value)
+    }
+}
+impl From<serde_json::Error> for UserStoreError {
+    fn from(value: serde_json::Error) -> Self {
+        Self::Json(value)
+    }
+}
+
+fn load_user_climbs(
+    mut kilter: ResMut<KilterData>,
+    mut saved: ResMut<SavedClimbs>,
+    mut toasts: EventWriter<ToastEvent>,
+) {
+    let stored = match backend::load() {
+        Ok(stored) => stored,
+        Err(e) => {
+            warn!("Failed to load user climbs. {}", e);
+            toasts.write(ToastEvent(
+                "Failed to load your climbs, so changes won't be saved".to_string(),
+            ));
+            return;
+        }
+    };
+
+    let mut skipped = 0;
+    for stored in stored {
+        let StoredClimb { origin, mut climb } = match stored {
+            Ok(stored) => stored,
+            Err(e) => {
+                warn!("Skipped saved climb. {}", e);
+                skipped += 1;
+                continue;
+            }
+        };
+
+        climb.origin = origin;
+        // Edited climbs replace the database climb with the same uuid.
+        kilter.insert_climb(climb);
+    }
+
+    if skipped > 0 {
+        toasts.write(ToastEvent(format!(
+            "Skipped {skipped} saved climbs that couldn't be read"
+        )));
+    }
+
+    saved.climbs = kilter
+        .climbs()
+        .values()
+        .filter(|climb| climb.origin.is_local())
+        .filter_map(|climb| Some((climb.uuid.clone(), serialize_climb(climb)?)))
+        .collect();
+    saved.generation = kilter.climbs_generation();
+    saved.loaded = true;
+
+    info!("Loaded user climbs");
+}
+
+fn save_user_climbs(kilter: Res<KilterData>, mut saved: ResMut<SavedClimbs>) {
+    if !saved.loaded || !kilter.is_changed() || kilter.climbs_generation() == saved.generation {
+        return;
+    }
+
+    let mut changed = vec![];
+    let mut serialized = vec![];
+    let mut removed = vec![];
+
+    for uuid in kilter.climbs_changed_since(saved.generation) {
+        match kilter.climb(uuid).filter(|climb| climb.origin.is_local()) {
+            Some(climb) => {
+                let Some(json) = serialize_climb(climb) else {
+                    continue;
+                };
+                if saved.climbs.get(uuid) != Some(&json) {
+                    changed.push(StoredClimb {
+                        origin: climb.origin,
+                        climb: climb.clone(),
+                    });
+                    serialized.push((uuid.to_string(), json));
+                }
+            }
+            None if saved.climbs.contains_key(uuid) => removed.push(uuid.to_string()),
+            None => {}
+        }
+    }
+
+    if !changed.is_empty() || !removed.is_empty() {
+        if let Err(e) = backend::save(&changed, &removed) {
+            warn!("Failed to save user climbs. {}", e);
+            return;
+        }
+    }
+
+    saved.climbs.extend(serialized);
+    for uuid in &removed {
+        saved.climbs.remove(uuid);
+    }
+    saved.generation = kilter.climbs_generation();
+}
+
+/// A climb that didn't come from the database, as it's saved.
+fn serialize_climb(climb: &Climb) -> Option<String> {
+    let stored = StoredClimb {
+        origin: climb.origin,
+        climb: climb.clone(),
+    };
+
+    match serde_json::to_string(&stored) {
+        Ok(json) => Some(json),
+        Err(e) => {
+            warn!("Failed to serialize user climb. {}", e);
+            None
+        }
+    }
+}
+
+fn load_missing_sets(mut active: ResMut<ActiveLayout>, mut saved: ResMut<SavedMissingSets>) {
+    match backend::load_missing_sets() {
+        Ok(Some(set_ids)) => active.missing_sets = set_ids.into_iter().collect(),
+        Ok(None) => {}
+        Err(e) => {
+            warn!("Failed to load missing sets. {}", e);
+            return;
+        }
+    }
+
+    saved.set_ids = active.missing_sets.clone();
+    saved.loaded = true;
+}
+
+fn save_missing_sets(active: Res<ActiveLayout>, mut saved: ResMut<SavedMissingSets>) {
+    if !saved.loaded || !active.is_changed() || active.missing_sets == saved.set_ids {
+        return;
+    }
+
+    let mut set_ids: Vec<_> = active.missing_sets.iter().copied().collect();
+    set_ids.sort();
+
+    if let Err(e) = backend::save_missing_sets(&set_ids) {
+        warn!("Failed to save missing sets. {}", e);
+        return;
+    }
+
+    saved.set_ids = active.missing_sets.clone();
+}
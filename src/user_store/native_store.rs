@@ -0,0 +1,79 @@
+use rusqlite::{params, Connection, OptionalExtension};
+
+use super::{StoredClimb, UserStoreError};
+
+/// Kept next to the climb database, separate so that updating the database
+/// doesn't lose the user's climbs.
+const PATH: &str = "../kilter_brain_data/user.sqlite3";
+
+fn open() -> Result<Connection, UserStoreError> {
+    let conn = Connection::open(PATH)?;
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS climbs (uuid TEXT PRIMARY KEY, data TEXT NOT NULL)",
+        (),
+    )?;
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS settings (key TEXT PRIMARY KEY, value TEXT NOT NULL)",
+        (),
+    )?;
+    Ok(conn)
+}
+
+/// Loads every saved climb, with an error in place of any that can't be read.
+pub fn load() -> Result<Vec<Result<StoredClimb, UserStoreError>>, UserStoreError> {
+    let conn = open()?;
+    let mut stmt = conn.prepare("SELECT data FROM climbs ORDER BY rowid")?;
+    let rows = stmt.query_map((), |row| row.get::<_, String>(0))?;
+
+    Ok(rows.map(|data| Ok(serde_json::from_str(&data?)?)).collect())
+}
+
+/// Writes `changed` climbs and deletes `removed` ones, leaving the rest as
+/// they are.
+pub fn save(changed: &[StoredClimb], removed: &[String]) -> Result<(), UserStoreError> {
+    let mut conn = open()?;
+    let tx = conn.transaction()?;
+
+    {
+        let mut stmt = tx.prepare(
+            "INSERT INTO climbs (uuid, data) VALUES (?1, ?2)
+            ON CONFLICT (uuid) DO UPDATE SET data = excluded.data",
+        )?;
+        for stored in changed {
+            stmt.execute(params![stored.climb.uuid, serde_json::to_string(stored)?])?;
+        }
+
+        let mut stmt = tx.prepare("DELETE FROM climbs WHERE uuid = ?1")?;
+        for uuid in removed {
+            stmt.execute(params![uuid])?;
+        }
+    }
+
+    tx.commit()?;
+    Ok(())
+}
+
+/// The ids of sets that aren't installed, or `None` if they were never saved.
+pub fn load_missing_sets() -> Result<Option<Vec<u32>>, UserStoreError> {
+    let conn = open()?;
+    let value: Option<String> = conn
+        .query_row(
+            "SELECT value FROM settings WHERE key = 'missing_sets'",
+            (),
+            |row| row.get(0),
+        )
+        .optional()?;
+
+    Ok(value
+        .map(|value| serde_json::from_str(&value))
+        .transpose()?)
+}
+
+pub fn save_missing_sets(set_ids: &[u32]) -> Result<(), UserStoreError> {
+    open()?.execute(
+        "INSERT INTO settings (key, value) VALUES ('missing_sets', ?1)
+        ON CONFLICT (key) DO UPDATE SET value = excluded.value",
+        params![serde_json::to_string(set_ids)?],
+    )?;
+    Ok(())
+}
@@ -0,0 +1,72 @@
+use serde_json::Value;
+
+use super::{StoredClimb, UserStoreError};
+
+const KEY: &str = "kilter_brain.user_climbs";
+const MISSING_SETS_KEY: &str = "kilter_brain.missing_sets";
+
+fn local_storage() -> Result<web_sys::Storage, UserStoreError> {
+    web_sys::window()
+        .and_then(|window| window.local_storage().ok().flatten())
+        .ok_or_else(|| UserStoreError::Storage("localStorage is unavailable".to_string()))
+}
+
+/// Loads every saved climb, with an error in place of any that can't be read.
+pub fn load() -> Result<Vec<Result<StoredClimb, UserStoreError>>, UserStoreError> {
+    Ok(stored_values()?
+        .into_iter()
+        .map(|value| Ok(serde_json::from_value(value)?))
+        .collect())
+}
+
+/// Writes `changed` climbs and deletes `removed` ones, keeping the rest as
+/// they are, even those that can't be read.
+pub fn save(changed: &[StoredClimb], removed: &[String]) -> Result<(), UserStoreError> {
+    let mut values = stored_values()?;
+    let uuid = |value: &Value| {
+        value
+            .pointer("/climb/uuid")
+            .and_then(Value::as_str)
+            .map(str::to_string)
+    };
+
+    values.retain(|value| uuid(value).is_none_or(|uuid| !removed.contains(&uuid)));
+    for stored in changed {
+        let value = serde_json::to_value(stored)?;
+        match values
+            .iter_mut()
+            .find(|v| uuid(v).as_ref() == Some(&stored.climb.uuid))
+        {
+            Some(existing) => *existing = value,
+            None => values.push(value),
+        }
+    }
+
+    local_storage()?
+        .set_item(KEY, &serde_json::to_string(&values)?)
+        .map_err(|e| UserStoreError::Storage(format!("{e:?}")))
+}
+
+/// The saved climbs, undecoded so that one bad climb doesn't lose the rest.
+fn stored_values() -> Result<Vec<Value>, UserStoreError> {
+    let Ok(Some(data)) = local_storage()?.get_item(KEY) else {
+        return Ok(vec![]);
+    };
+
+    Ok(serde_json::from_str(&data)?)
+}
+
+/// The ids of sets that aren't installed, or `None` if they were never saved.
+pub fn load_missing_sets() -> Result<Option<Vec<u32>>, UserStoreError> {
+    let Ok(Some(data)) = local_storage()?.get_item(MISSING_SETS_KEY) else {
+        return Ok(None);
+    };
+
+    Ok(Some(serde_json::from_str(&data)?))
+}
+
+pub fn save_missing_sets(set_ids: &[u32]) -> Result<(), UserStoreError> {
+    local_storage()?
+        .set_item(MISSING_SETS_KEY, &serde_json::to_string(set_ids)?)
+        .map_err(|e| UserStoreError::Storage(format!("{e:?}")))
+}